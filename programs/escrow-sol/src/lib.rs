@@ -3,27 +3,74 @@ use anchor_lang::prelude::*;
 use anchor_lang::solana_program::clock::Clock;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer, Mint, Token, TokenAccount, Transfer},
+    associated_token::get_associated_token_address,
+    metadata::{mpl_token_metadata::types::TokenStandard, MetadataAccount},
+    token::{close_account, spl_token::native_mint, sync_native, transfer, CloseAccount, Mint, SyncNative, Token, TokenAccount, Transfer},
 };
 
 declare_id!("qbuMdeYxYJXBjU6C6qFKjZKjXmrU83eDQomHdrch826");
 
+#[cfg(feature = "authorized-launcher")]
 pub const AUTHORIZED_LAUNCHER: Pubkey = pubkey!("9FEDyP1t345xFKVrJPN2TgQvQEJGz8KXE2xPV6TVXYY6");
+pub const CONFIG_VERSION: u8 = 1;
+pub const ESCROW_VERSION: u8 = 1;
+pub const NATIVE_MINT: Pubkey = native_mint::ID;
+pub const DEFAULT_NAMESPACE: Pubkey = Pubkey::new_from_array([0; 32]);
+pub const MAX_TAX_TIERS: usize = 4;
+pub const MAX_ASSET_LEGS: usize = 3;
+pub const MAX_PAYEES: usize = 5;
+pub const DEFAULT_CONFIG_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+pub const MAX_CONFIG_TIMELOCK: i64 = 30 * 24 * 60 * 60;
+pub const JUDGE_WINDOW: i64 = 28 * 24 * 60 * 60;
+pub const PAUSE_CREATE: u8 = 1 << 0;
+pub const PAUSE_DEPOSIT: u8 = 1 << 1;
+pub const PAUSE_ALL: u8 = PAUSE_CREATE | PAUSE_DEPOSIT;
 
 #[program]
 pub mod test {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, tax: u16, fee: u8) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>, tax: u16, fee: u16) -> Result<()> {
         let config = &mut ctx.accounts.config;
-        config.judge = *ctx.accounts.owner.key;
-        config.treasury = *ctx.accounts.treasury.key;
-        config.tax = tax;
-        config.fee = fee;
-        config.bump = ctx.bumps.config;
+        config.init(
+            DEFAULT_NAMESPACE,
+            ctx.accounts.owner.key(),
+            ctx.accounts.treasury.key(),
+            tax,
+            fee,
+            ctx.bumps.config,
+        )?;
+        ctx.accounts.global_stats.open(config.key(), None, ctx.bumps.global_stats);
+        emit!(ConfigCreated {
+            address: config.key(),
+            namespace: config.namespace,
+            treasury: config.treasury,
+            admin: config.admin,
+            judge: config.judge,
+            tax: config.tax,
+            fee: config.fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    // Partner marketplaces get their own judge, treasury and fees under their own key
+    pub fn initialize_namespace(ctx: Context<InitializeNamespace>, tax: u16, fee: u16) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.init(
+            ctx.accounts.owner.key(),
+            ctx.accounts.owner.key(),
+            ctx.accounts.treasury.key(),
+            tax,
+            fee,
+            ctx.bumps.config,
+        )?;
+        ctx.accounts.global_stats.open(config.key(), None, ctx.bumps.global_stats);
         emit!(ConfigCreated {
             address: config.key(),
+            namespace: config.namespace,
             treasury: config.treasury,
+            admin: config.admin,
             judge: config.judge,
             tax: config.tax,
             fee: config.fee,
@@ -32,37 +79,184 @@ pub mod test {
         Ok(())
     }
 
-    pub fn update_config(ctx: Context<UpdateContext>, updates: ConfigUpdateArgs) -> Result<()> {
+    pub fn queue_config_update(ctx: Context<UpdateContext>, updates: ConfigUpdateArgs) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        if config.queued_update.is_some() {
+            return Err(error!(ErrorCode::ConfigUpdateAlreadyQueued));
+        }
+        if updates.tax.is_some_and(|new_tax| new_tax > 2000) {
+            return Err(error!(ErrorCode::TaxTooHigh));
+        }
+        if updates.fee.is_some_and(|new_fee| new_fee > 2000) {
+            return Err(error!(ErrorCode::FeeTooHigh));
+        }
+        if updates.judge_share.is_some_and(|new_judge_share| new_judge_share > 10000) {
+            return Err(error!(ErrorCode::JudgeShareTooHigh));
+        }
+        if updates.timelock.is_some_and(|new_timelock| !(0..=MAX_CONFIG_TIMELOCK).contains(&new_timelock)) {
+            return Err(error!(ErrorCode::InvalidTimelock));
+        }
+        if let Some(mint_policy) = &updates.mint_policy {
+            // mint policy lands through its own instruction, so it can't share the queue with config fields
+            if updates.has_config_changes() {
+                return Err(error!(ErrorCode::MixedConfigUpdate));
+            }
+            mint_policy.validate()?;
+        }
+        // escrow users get `timelock` seconds to walk away before this lands
+        let now = Clock::get()?.unix_timestamp;
+        let eta = now + config.timelock;
+        config.queued_update = Some(updates.clone());
+        config.queued_eta = eta;
+        emit!(ConfigUpdateQueued {
+            address: config.key(),
+            updates,
+            eta,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn execute_config_update(ctx: Context<UpdateContext>) -> Result<()> {
         let config = &mut ctx.accounts.config;
+        let now = Clock::get()?.unix_timestamp;
+        let Some(updates) = config.queued_update.clone() else {
+            return Err(error!(ErrorCode::NoQueuedConfigUpdate));
+        };
+        if now < config.queued_eta {
+            return Err(error!(ErrorCode::ConfigUpdateTimelocked));
+        }
+        if updates.mint_policy.is_some() {
+            return Err(error!(ErrorCode::MintPolicyQueued));
+        }
+        config.queued_update = None;
+        config.queued_eta = 0;
         if let Some(new_treasury) = updates.treasury {
             config.treasury = new_treasury;
         }
         if let Some(new_tax) = updates.tax {
-            if new_tax > 2000 {
-                return Err(error!(ErrorCode::TaxTooHigh));
-            }
             config.tax = new_tax;
         }
         if let Some(new_fee) = updates.fee {
-            if new_fee > 20 {
-                return Err(error!(ErrorCode::FeeTooHigh));
-            }
             config.fee = new_fee;
         }
+        if let Some(new_judge_share) = updates.judge_share {
+            config.judge_share = new_judge_share;
+        }
+        if let Some(new_timelock) = updates.timelock {
+            config.timelock = new_timelock;
+        }
+        if let Some(new_guardian) = updates.guardian {
+            config.guardian = new_guardian;
+        }
         if let Some(new_judge) = updates.pending_judge {
             config.pending_judge = Some(new_judge);
             emit!(JudgeNominated {
                 address: config.key(),
                 pending_judge: new_judge,
-                timestamp: Clock::get()?.unix_timestamp,
+                timestamp: now,
+            })
+        }
+        if let Some(new_admin) = updates.pending_admin {
+            config.pending_admin = Some(new_admin);
+            emit!(AdminNominated {
+                address: config.key(),
+                pending_admin: new_admin,
+                timestamp: now,
             })
         }
         emit!(ConfigUpdated {
             address: config.key(),
             treasury: config.treasury,
+            pending_admin: config.pending_admin,
             pending_judge: config.pending_judge,
             tax: config.tax,
             fee: config.fee,
+            judge_share: config.judge_share,
+            timelock: config.timelock,
+            guardian: config.guardian,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_config_update(ctx: Context<UpdateContext>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let Some(updates) = config.queued_update.take() else {
+            return Err(error!(ErrorCode::NoQueuedConfigUpdate));
+        };
+        config.queued_eta = 0;
+        emit!(ConfigUpdateCancelled {
+            address: config.key(),
+            updates,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPausedContext>, paused: u8) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let authority = ctx.accounts.authority.key();
+        config.check_pause(authority, paused)?;
+        config.paused = paused;
+        emit!(PauseUpdated {
+            address: config.key(),
+            paused,
+            updated_by: authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfigContext>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        let (from_version, config) = ConfigAccount::from_legacy(&config_info.try_borrow_data()?)?;
+        if from_version == 0 && config_info.key() != default_config_address() {
+            // launch configs only ever lived at [b"config"]
+            return Err(error!(ErrorCode::WrongConfig));
+        }
+        realloc_for_migration(
+            &config_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + ConfigAccount::INIT_SPACE,
+        )?;
+        config.write_migrated(&config_info)?;
+        // configs from before stats need their rollup, escrows only ever settle against an existing one
+        ctx.accounts.global_stats.open(config_info.key(), None, ctx.bumps.global_stats);
+        emit!(AccountMigrated {
+            address: config_info.key(),
+            from_version,
+            to_version: CONFIG_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn migrate_escrow(ctx: Context<MigrateEscrowContext>) -> Result<()> {
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let (from_version, mut escrow) = EscrowAccount::from_legacy(&escrow_info.try_borrow_data()?)?;
+        if from_version == 0 {
+            // launch escrows never recorded their deposit, read it off what the escrow actually holds
+            escrow.deposited = match escrow.token_mint {
+                None => escrow_info.lamports().saturating_sub(Rent::get()?.minimum_balance(escrow_info.data_len())),
+                Some(mint) => match &ctx.accounts.escrow_token_account {
+                    Some(vault) if vault.key() == get_associated_token_address(&escrow_info.key(), &mint) => vault.amount,
+                    _ => return Err(error!(ErrorCode::MissingTokenAccounts)),
+                },
+            };
+        }
+        realloc_for_migration(
+            &escrow_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + EscrowAccount::INIT_SPACE,
+        )?;
+        escrow.write_migrated(&escrow_info)?;
+        emit!(AccountMigrated {
+            address: escrow_info.key(),
+            from_version,
+            to_version: ESCROW_VERSION,
             timestamp: Clock::get()?.unix_timestamp,
         });
         Ok(())
@@ -85,426 +279,1758 @@ pub mod test {
         Ok(())
     }
 
+    pub fn accept_admin_seat(ctx: Context<AcceptAdminSeatContext>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let old_admin = config.admin;
+        if let Some(new_admin) = config.pending_admin {
+            config.admin = new_admin;
+            config.pending_admin = None;
+            emit!(AdminAccepted {
+                address: config.key(),
+                old_admin,
+                new_admin: config.admin,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn set_tax_tiers(ctx: Context<SetTaxTiersContext>, mint: Pubkey, tiers: Vec<TaxTier>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.config.take_mint_policy(MintPolicyUpdate::TaxTiers { mint, tiers: tiers.clone() }, now)?;
+        let tax_tiers = &mut ctx.accounts.tax_tiers;
+        tax_tiers.mint = mint;
+        tax_tiers.tiers = tiers;
+        tax_tiers.bump = ctx.bumps.tax_tiers;
+        emit!(TaxTiersUpdated {
+            address: tax_tiers.key(),
+            mint,
+            tiers: tax_tiers.tiers.clone(),
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn set_fee_bounds(ctx: Context<SetFeeBoundsContext>, mint: Pubkey, min_fee: u64, max_fee: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.config.take_mint_policy(MintPolicyUpdate::FeeBounds { mint, min_fee, max_fee }, now)?;
+        let fee_bounds = &mut ctx.accounts.fee_bounds;
+        fee_bounds.mint = mint;
+        fee_bounds.min_fee = min_fee;
+        fee_bounds.max_fee = max_fee;
+        fee_bounds.bump = ctx.bumps.fee_bounds;
+        emit!(FeeBoundsUpdated {
+            address: fee_bounds.key(),
+            mint,
+            min_fee,
+            max_fee,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    pub fn add_allowed_mint(ctx: Context<AddAllowedMintContext>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mint_account = &ctx.accounts.mint_account;
+        ctx.accounts.config.take_mint_policy(MintPolicyUpdate::AllowMint { mint: mint_account.key() }, now)?;
+        // a mint that can freeze the escrow's token account can trap funds forever
+        if mint_account.freeze_authority.is_some() {
+            return Err(error!(ErrorCode::MintHasFreezeAuthority));
+        }
+        let allowed_mint = &mut ctx.accounts.allowed_mint;
+        allowed_mint.mint = mint_account.key();
+        allowed_mint.bump = ctx.bumps.allowed_mint;
+        emit!(MintAllowed {
+            address: allowed_mint.key(),
+            mint: allowed_mint.mint,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    // not timelocked - pulling a bad mint is a brake, like pausing, so the guardian can do it too
+    pub fn remove_allowed_mint(ctx: Context<RemoveAllowedMintContext>) -> Result<()> {
+        let allowed_mint = &ctx.accounts.allowed_mint;
+        emit!(MintRemoved {
+            address: allowed_mint.key(),
+            mint: allowed_mint.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn open_volume_account(ctx: Context<OpenVolumeContext>, mint: Pubkey) -> Result<()> {
+        let volume = &mut ctx.accounts.volume;
+        volume.wallet = ctx.accounts.wallet.key();
+        volume.mint = mint;
+        volume.volume = 0;
+        volume.bump = ctx.bumps.volume;
+        Ok(())
+    }
+
+    pub fn open_reputation(ctx: Context<OpenReputationContext>, wallet: Pubkey) -> Result<()> {
+        let reputation = &mut ctx.accounts.reputation;
+        reputation.wallet = wallet;
+        reputation.bump = ctx.bumps.reputation;
+        Ok(())
+    }
+
     pub fn create_escrow(ctx: Context<CreateEscrowContext>, args: EscrowCreationArgs) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         let config = &ctx.accounts.config;
-        if ((args.amount * (config.tax as u64))/ 10000) < 1 {
-            return Err(error!(ErrorCode::InvalidEscrowAmount));
+        // wSOL is just SOL, it never needs the allowlist
+        if args.token_mint.is_some_and(|mint| mint != NATIVE_MINT) && ctx.accounts.allowed_mint.is_none() {
+            return Err(error!(ErrorCode::MintNotAllowed));
         }
-        escrow.payer = ctx.accounts.payer.key();
-        escrow.payee = args.payee;
-        let now = Clock::get()?.unix_timestamp;
-        escrow.creation_time = now;
-        escrow.deadline = now + (14 * 24 * 60 * 60);
-        escrow.judge_deadline = escrow.deadline + (28 * 24 * 60 * 60);
-        escrow.amount = args.amount;
-        escrow.token_mint = args.token_mint;
-        escrow.tax = config.tax;
-        escrow.fee = config.fee;
-        escrow.bump = ctx.bumps.escrow;
+        let tax = config.escrow_tax(ctx.accounts.tax_tiers.as_deref(), ctx.accounts.payer_volume.as_deref(), args.amount);
+        EscrowAccount::check_amount(args.amount, tax)?;
+        escrow.open(config, ctx.accounts.payer.key(), &args, tax, ctx.accounts.fee_bounds.as_deref(), ctx.bumps.escrow)?;
         emit!(EscrowCreated {
             address: escrow.key(),
+            config: escrow.config,
             payer: escrow.payer,
             payee: escrow.payee,
             amount: escrow.amount,
             token_mint: escrow.token_mint,
             timestamp: Clock::get()?.unix_timestamp,
         });
+        ctx.accounts.global_stats.record(StatsUpdate::Created { amount: escrow.amount });
+        ctx.accounts.mint_stats.open(escrow.config, Some(escrow.token_mint.unwrap_or_default()), ctx.bumps.mint_stats).record(StatsUpdate::Created { amount: escrow.amount });
         Ok(())
     }
 
-    pub fn dispute_escrow(ctx: Context<DisputeEscrowContext>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        let user = &ctx.accounts.user;
+    pub fn create_and_fund_sol_escrow(ctx: Context<CreateAndFundSolanaContext>, args: EscrowCreationArgs) -> Result<()> {
         let config = &ctx.accounts.config;
-        // Judges can't get involved until after the deadline
-        if user.key() == config.judge {
-            let now = Clock::get()?.unix_timestamp;
-            if now <= escrow.deadline {
-                return Err(error!(ErrorCode::UninvolvedUser));
-            }
-        }
-        emit!(EscrowDisputed {
+        let tax = config.escrow_tax(ctx.accounts.tax_tiers.as_deref(), ctx.accounts.payer_volume.as_deref(), args.amount);
+        EscrowAccount::check_amount(args.amount, tax)?;
+        ctx.accounts.escrow.open(config, ctx.accounts.payer.key(), &args, tax, ctx.accounts.fee_bounds.as_deref(), ctx.bumps.escrow)?;
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                },
+            ),
+            args.amount,
+        )?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.deposited = escrow.amount;
+        let now = Clock::get()?.unix_timestamp;
+        emit!(EscrowCreated {
             address: escrow.key(),
+            config: escrow.config,
             payer: escrow.payer,
             payee: escrow.payee,
-            disputed_by: user.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+            amount: escrow.amount,
+            token_mint: escrow.token_mint,
+            timestamp: now,
         });
-        escrow.disputed = true;
+        emit!(EscrowDeposited {
+            address: escrow.key(),
+            amount: escrow.deposited,
+            token_mint: escrow.token_mint,
+            timestamp: now,
+        });
+        ctx.accounts.global_stats.record(StatsUpdate::Created { amount: escrow.amount });
+        ctx.accounts.mint_stats.open(escrow.config, Some(Pubkey::default()), ctx.bumps.mint_stats).record(StatsUpdate::Created { amount: escrow.amount });
         Ok(())
     }
 
-    pub fn judge_sol_escrow(ctx: Context<JudgeSolanaContext>, decision: bool) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        let treasury_info = &mut ctx.accounts.treasury.to_account_info();
-        let escrow_info = &mut ctx.accounts.escrow.to_account_info();
-        // get the rent
-        let rent = Rent::get()?;
-        let rent_exemption = rent.minimum_balance(escrow_info.data_len());
-        let amount = escrow.amount + rent_exemption;
-        // % fee for requiring judgement
-        let fee = amount * ((escrow.fee as u64) / 100);
-        if !decision {
-            let payer_info = &mut ctx.accounts.payer.to_account_info();
-            let payer_amount = amount - fee;
-            **escrow_info.try_borrow_mut_lamports()? -= amount;
-            **treasury_info.try_borrow_mut_lamports()? += fee;
-            **payer_info.try_borrow_mut_lamports()? += payer_amount;
-            emit!(EscrowJudged {
-                address: escrow.key(),
-                winner: escrow.payer,
-                amount_awarded: payer_amount,
-                fee_collected: fee,
-                token_mint: escrow.token_mint,
-                timestamp: Clock::get()?.unix_timestamp,
-            });
+    pub fn create_and_fund_token_escrow(ctx: Context<CreateAndFundTokenContext>, args: EscrowCreationArgs) -> Result<()> {
+        if args.token_mint != Some(NATIVE_MINT) && ctx.accounts.allowed_mint.is_none() {
+            return Err(error!(ErrorCode::MintNotAllowed));
         }
-        if decision {
-            let payee_info = &mut ctx.accounts.payee.to_account_info();
-            let payee_amount = amount - fee;
-            **escrow_info.try_borrow_mut_lamports()? -= amount;
-            **treasury_info.try_borrow_mut_lamports()? += fee;
-            **payee_info.try_borrow_mut_lamports()? += payee_amount;
-            emit!(EscrowJudged {
-                address: escrow.key(),
-                winner: escrow.payer,
-                amount_awarded: payee_amount,
-                fee_collected: fee,
-                token_mint: escrow.token_mint,
-                timestamp: Clock::get()?.unix_timestamp,
-            });
-        }
-        Ok(())
-    }
-
-    pub fn deposit_sol_funds(ctx: Context<DepositSolanaContext>) -> Result<()> {
-        let payer_info = &mut ctx.accounts.payer.to_account_info();
-        let escrow = &ctx.accounts.escrow;
-        let escrow_info = &mut ctx.accounts.escrow.to_account_info();
-        // get the rent
-        let rent = Rent::get()?;
-        let rent_exemption = rent.minimum_balance(escrow_info.data_len());
-        // deposit the sol
-        let amount = escrow.amount + rent_exemption;
-        **payer_info.try_borrow_mut_lamports()? -= amount;
-        **escrow_info.try_borrow_mut_lamports()? += amount;
+        let config = &ctx.accounts.config;
+        let tax = config.escrow_tax(ctx.accounts.tax_tiers.as_deref(), ctx.accounts.payer_volume.as_deref(), args.amount);
+        EscrowAccount::check_amount(args.amount, tax)?;
+        ctx.accounts.escrow.open(config, ctx.accounts.payer.key(), &args, tax, ctx.accounts.fee_bounds.as_deref(), ctx.bumps.escrow)?;
+        transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer_token_account.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            args.amount,
+        )?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.deposited = escrow.amount;
+        let now = Clock::get()?.unix_timestamp;
+        emit!(EscrowCreated {
+            address: escrow.key(),
+            config: escrow.config,
+            payer: escrow.payer,
+            payee: escrow.payee,
+            amount: escrow.amount,
+            token_mint: escrow.token_mint,
+            timestamp: now,
+        });
         emit!(EscrowDeposited {
             address: escrow.key(),
-            amount: amount,
+            amount: escrow.deposited,
             token_mint: escrow.token_mint,
-            timestamp: Clock::get()?.unix_timestamp,
+            timestamp: now,
         });
+        ctx.accounts.global_stats.record(StatsUpdate::Created { amount: escrow.amount });
+        ctx.accounts.mint_stats.open(escrow.config, escrow.token_mint, ctx.bumps.mint_stats).record(StatsUpdate::Created { amount: escrow.amount });
         Ok(())
     }
 
-    pub fn release_sol_funds(ctx: Context<ReleaseSolanaContext>) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        let payee_info = &mut ctx.accounts.payee.to_account_info();
-        let escrow_info = &mut ctx.accounts.escrow.to_account_info();
-        let treasury_info = &mut ctx.accounts.treasury.to_account_info();
-        // get the rent
-        let rent = Rent::get()?;
-        let rent_exemption = rent.minimum_balance(escrow_info.data_len());
-        let remaining_lamports = escrow_info.lamports();
-        let amount = remaining_lamports - rent_exemption;
-        // Do the taxes for the dao
-        let fee = amount * ((escrow.fee as u64) / 10000);
-        let payee_amount = amount - fee;
-        **escrow_info.try_borrow_mut_lamports()? -= amount;
-        **treasury_info.try_borrow_mut_lamports()? += fee;
-        **payee_info.try_borrow_mut_lamports()? += payee_amount;
-        emit!(EscrowReleased {
+    pub fn create_nft_escrow(ctx: Context<CreateNftEscrowContext>, args: EscrowCreationArgs, collection: Option<Pubkey>) -> Result<()> {
+        let mint = ctx.accounts.mint_account.key();
+        if args.token_mint != Some(mint) {
+            return Err(error!(ErrorCode::WrongToken));
+        }
+        if args.amount != 1 {
+            return Err(error!(ErrorCode::NftAmountFixed));
+        }
+        if ctx.accounts.allowed_mint.is_none() {
+            return Err(error!(ErrorCode::MintNotAllowed));
+        }
+        // metadata is optional, but a collection can only be checked against it
+        match (&ctx.accounts.metadata, collection) {
+            (None, None) => {}
+            (None, Some(_)) => return Err(error!(ErrorCode::InvalidNftMetadata)),
+            (Some(metadata), collection) => {
+                if metadata.mint != mint {
+                    return Err(error!(ErrorCode::InvalidNftMetadata));
+                }
+                // programmable NFTs are frozen in place and can't move with a plain token transfer
+                if matches!(metadata.token_standard, Some(TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition)) {
+                    return Err(error!(ErrorCode::InvalidNftMetadata));
+                }
+                if let Some(collection) = collection {
+                    if !metadata.collection.as_ref().is_some_and(|c| c.verified && c.key == collection) {
+                        return Err(error!(ErrorCode::InvalidNftMetadata));
+                    }
+                }
+            }
+        }
+        let config = &ctx.accounts.config;
+        // taxed like any escrow so its legs pay, the single token itself always rounds down to nothing.
+        // no check_amount, the whole point is an amount of 1
+        let tax = config.escrow_tax(ctx.accounts.tax_tiers.as_deref(), ctx.accounts.payer_volume.as_deref(), args.amount);
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.open(config, ctx.accounts.payer.key(), &args, tax, None, ctx.bumps.escrow)?;
+        escrow.nft = true;
+        emit!(EscrowCreated {
             address: escrow.key(),
-            amount: payee_amount,
-            tax_paid: fee,
+            config: escrow.config,
+            payer: escrow.payer,
+            payee: escrow.payee,
+            amount: escrow.amount,
             token_mint: escrow.token_mint,
             timestamp: Clock::get()?.unix_timestamp,
         });
+        ctx.accounts.global_stats.record(StatsUpdate::Created { amount: escrow.amount });
+        ctx.accounts.mint_stats.open(escrow.config, escrow.token_mint, ctx.bumps.mint_stats).record(StatsUpdate::Created { amount: escrow.amount });
         Ok(())
     }
 
-    pub fn return_sol_funds(ctx: Context<ReturnSolanaContext>) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        let payer_info = &mut ctx.accounts.payee.to_account_info();
-        let escrow_info = &mut ctx.accounts.escrow.to_account_info();
-        let treasury_info = &mut ctx.accounts.treasury.to_account_info();
-        // get the rent
-        let rent = Rent::get()?;
-        let rent_exemption = rent.minimum_balance(escrow_info.data_len());
-        let remaining_lamports = escrow_info.lamports();
-        let amount = remaining_lamports - rent_exemption;
-        // Do the taxes for the dao
-        let fee = amount * ((escrow.tax as u64) / 10000);
-        let payer_amount = amount - fee;
-        **escrow_info.try_borrow_mut_lamports()? -= amount;
-        **treasury_info.try_borrow_mut_lamports()? += fee;
-        **payer_info.try_borrow_mut_lamports()? += payer_amount;
-        emit!(EscrowReturned {
+    pub fn add_asset_leg(ctx: Context<AddAssetLegContext>, amount: u64) -> Result<()> {
+        let mint = ctx.accounts.mint_account.key();
+        if mint != NATIVE_MINT && ctx.accounts.allowed_mint.is_none() {
+            return Err(error!(ErrorCode::MintNotAllowed));
+        }
+        let escrow = &mut ctx.accounts.escrow;
+        if escrow.vesting.is_some() {
+            return Err(error!(ErrorCode::VestingIncompatible));
+        }
+        if escrow.recurring.is_some() {
+            return Err(error!(ErrorCode::RecurringIncompatible));
+        }
+        if !escrow.payees.is_empty() {
+            return Err(error!(ErrorCode::SplitIncompatible));
+        }
+        if escrow.legs.len() >= MAX_ASSET_LEGS {
+            return Err(error!(ErrorCode::TooManyAssetLegs));
+        }
+        if escrow.token_mint == Some(mint) || escrow.legs.iter().chain(&escrow.counter).any(|leg| leg.mint == mint) {
+            return Err(error!(ErrorCode::DuplicateAssetLeg));
+        }
+        if amount == 0 {
+            return Err(error!(ErrorCode::InvalidEscrowAmount));
+        }
+        escrow.legs.push(AssetLeg { mint, amount, deposited: 0 });
+        emit!(AssetLegAdded {
             address: escrow.key(),
-            amount: payer_amount,
-            tax_paid: fee,
-            token_mint: escrow.token_mint,
+            mint,
+            amount,
             timestamp: Clock::get()?.unix_timestamp,
         });
         Ok(())
     }
 
-    pub fn recover_sol_funds(ctx: Context<RecoverSolanaContext>) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+    pub fn add_counter_leg(ctx: Context<AddAssetLegContext>, amount: u64) -> Result<()> {
+        let mint = ctx.accounts.mint_account.key();
+        if mint != NATIVE_MINT && ctx.accounts.allowed_mint.is_none() {
+            return Err(error!(ErrorCode::MintNotAllowed));
+        }
         let escrow = &mut ctx.accounts.escrow;
-        if now <= escrow.judge_deadline {
-            return Err(error!(ErrorCode::RecoverTooEarly));
+        if escrow.is_swap() {
+            return Err(error!(ErrorCode::CounterLegAlreadySet));
         }
-        let escrow_info = &escrow.to_account_info();
-        let payer_info = &mut ctx.accounts.payer.to_account_info();
-        let rent = Rent::get()?;
-        let rent_exemption = rent.minimum_balance(escrow_info.data_len());
-        let remaining_lamports = escrow_info.lamports();
-        let amount = remaining_lamports - rent_exemption;
-        **escrow_info.try_borrow_mut_lamports()? -= amount;
-        **payer_info.try_borrow_mut_lamports()? += amount;
-        emit!(EscrowRecovered {
+        if escrow.vesting.is_some() {
+            return Err(error!(ErrorCode::VestingIncompatible));
+        }
+        if escrow.recurring.is_some() {
+            return Err(error!(ErrorCode::RecurringIncompatible));
+        }
+        if !escrow.payees.is_empty() {
+            return Err(error!(ErrorCode::SplitIncompatible));
+        }
+        if escrow.token_mint == Some(mint) || escrow.legs.iter().any(|leg| leg.mint == mint) {
+            return Err(error!(ErrorCode::DuplicateAssetLeg));
+        }
+        if amount == 0 {
+            return Err(error!(ErrorCode::InvalidEscrowAmount));
+        }
+        escrow.counter = Some(AssetLeg { mint, amount, deposited: 0 });
+        emit!(CounterLegAdded {
             address: escrow.key(),
-            amount: amount,
-            token_mint: escrow.token_mint,
+            mint,
+            amount,
             timestamp: Clock::get()?.unix_timestamp,
         });
         Ok(())
     }
 
-    pub fn deposit_token_funds(ctx: Context<DepositTokenContext>) -> Result<()> {
+    pub fn deposit_counter_leg(ctx: Context<DepositCounterLegContext>) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
-        if let Some(_token_mint_pubkey) = escrow.token_mint {
-            let payer = &mut ctx.accounts.payer;
-            let payer_token_account = &ctx.accounts.payer_token_account;
-            let escrow_token_account = &ctx.accounts.escrow_token_account;
-            transfer(
+        if escrow.swap_expired(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::SwapExpired));
+        }
+        let address = escrow.key();
+        let Some(counter) = escrow.counter.as_mut() else {
+            return Err(error!(ErrorCode::NotASwap));
+        };
+        transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                from: payer_token_account.to_account_info(),
-                to: escrow_token_account.to_account_info(),
-                authority: payer.to_account_info(),
+                    from: ctx.accounts.payee_token_account.to_account_info(),
+                    to: ctx.accounts.counter_vault.to_account_info(),
+                    authority: ctx.accounts.payee.to_account_info(),
                 },
             ),
-            escrow.amount,
-            )?;
-            emit!(EscrowDeposited {
-            address: escrow.key(),
-            amount: escrow.amount,
-            token_mint: escrow.token_mint,
+            counter.amount,
+        )?;
+        counter.deposited = counter.amount;
+        emit!(EscrowDeposited {
+            address,
+            amount: counter.amount,
+            token_mint: Some(counter.mint),
             timestamp: Clock::get()?.unix_timestamp,
-            });
-        } else {
-            return Err(error!(ErrorCode::EscrowNotToken))
-        }
+        });
         Ok(())
     }
 
-    pub fn judge_token_escrow(ctx: Context<JudgeTokenContext>, decision: bool) -> Result<()> {
+    // both sides are in, so the trade goes through: the payee gets the main asset and legs, the payer gets the counter
+    // leg, each minus tax. remaining accounts: the counter ATA, treasury's and payer's token accounts for the counter
+    // mint, then the usual [leg ATA, treasury, payee] per asset leg
+    pub fn settle_swap<'info>(ctx: Context<'_, '_, '_, 'info, SettleSwapContext<'info>>) -> Result<()> {
         let escrow = &ctx.accounts.escrow;
-        let treasury_token_account = &mut ctx.accounts.treasury_token_account;
-        let payer_token_account = &mut ctx.accounts.payer_token_account;
-        let payee_token_account = &mut ctx.accounts.payee_token_account;
-        let escrow_token_account = &mut ctx.accounts.escrow_token_account;
-        if let Some(_token_mint_pubkey) = escrow.token_mint {
-            // calculate fee
-            let init_amount = escrow.amount;
-            // convert to decimals
-            let fee = (init_amount * (escrow.fee as u64)) / 100; // percentage fee for judgement
-            let amount = init_amount - fee;
-            
-            transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                         from: escrow_token_account.to_account_info(),
-                         to: treasury_token_account.to_account_info(),
-                         authority: escrow.to_account_info()
-                    },
-                ),
-                fee,
-            )?;
-            // handle decision
-            if decision {
-                transfer(
-                    CpiContext::new(
-                        ctx.accounts.token_program.to_account_info(),
-                        Transfer {
-                            from: escrow_token_account.to_account_info(),
-                            to: payee_token_account.to_account_info(),
-                            authority: escrow.to_account_info(),
-                        },
-                    ),
-                    amount,
-                )?;
-                emit!(EscrowJudged {
-                    address: escrow.key(),
-                    winner: escrow.payee,
-                    amount_awarded: amount,
-                    fee_collected: fee,
-                    token_mint: escrow.token_mint,
-                    timestamp: Clock::get()?.unix_timestamp,
-                });
-            } else {
-                transfer(
-                    CpiContext::new(
-                        ctx.accounts.token_program.to_account_info(),
-                        Transfer {
-                            from: escrow_token_account.to_account_info(),
-                            to: payer_token_account.to_account_info(),
-                            authority: escrow.to_account_info(),
-                        },
-                    ),
-                    amount,
-                )?;
-                emit!(EscrowJudged {
-                    address: escrow.key(),
-                    winner: escrow.payer,
-                    amount_awarded: amount,
-                    fee_collected: fee,
-                    token_mint: escrow.token_mint,
-                    timestamp: Clock::get()?.unix_timestamp,
-                });
-            }
-        } else {
-            return Err(error!(ErrorCode::EscrowNotToken))
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
         };
+        let pot = escrow.deposited;
+        let fee = escrow.tax_due(pot);
+        let amount = pot - fee;
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), fee)?;
+        vault.pay_winner(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), amount)?;
+        let split = |pot| {
+            let tax = escrow.tax_due(pot);
+            vec![tax, pot - tax]
+        };
+        let legs = vault.pay_counter(ctx.remaining_accounts, &[&ctx.accounts.treasury, &ctx.accounts.payer], split)?;
+        vault.pay_legs(legs, &[&ctx.accounts.treasury, &ctx.accounts.payee], split)?;
+        vault.close_vault()?;
+        let counter = escrow.counter.as_ref().ok_or(error!(ErrorCode::NotASwap))?;
+        let counter_fee = escrow.tax_due(counter.deposited);
+        emit!(SwapSettled {
+            address: escrow.key(),
+            executed: true,
+            judged: false,
+            amount,
+            fees_paid: fee,
+            counter_amount: counter.deposited - counter_fee,
+            counter_fees_paid: counter_fee,
+            token_mint: escrow.token_mint,
+            counter_mint: counter.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.completed_as_payer += 1;
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.completed_as_payee += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Released { tax: fee });
         Ok(())
     }
 
-    pub fn release_token_escrow(ctx: Context<ReleaseTokenContext>) -> Result<()> {
+    // the trade never happened: everyone gets back exactly what they put in, no tax. open to either side once the
+    // deadline passes with a side unfunded, or once the judge deadline passes if it was funded or disputed.
+    // remaining accounts: the counter ATA and the payee's token account if the payee deposited, then [leg ATA, payer]
+    // per asset leg if the payer deposited
+    pub fn cancel_swap<'info>(ctx: Context<'_, '_, '_, 'info, CancelSwapContext<'info>>) -> Result<()> {
         let escrow = &ctx.accounts.escrow;
-        // calculate the tax
-        let init_amount = escrow.amount;
-        let fee = (init_amount * (escrow.tax as u64)) / 10000; // basis point tax for being available for judgement
-        let amount = init_amount - fee;
-        let escrow_token_account = &mut ctx.accounts.escrow_token_account;
-        let payee_token_account = &mut ctx.accounts.payee_token_account;
-        let treasury_token_account = &mut ctx.accounts.treasury_token_account;
-        transfer(CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: escrow_token_account.to_account_info(),
-                to: treasury_token_account.to_account_info(),
-                authority: escrow.to_account_info(),
-            },
-        ),
-        fee,
-        )?;
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: escrow_token_account.to_account_info(),
-                    to: payee_token_account.to_account_info(),
-                    authority: escrow.to_account_info(),
-                },
-            ),
+        if Clock::get()?.unix_timestamp <= escrow.swap_open_until() {
+            return Err(error!(ErrorCode::SwapStillOpen));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let legs = vault.pay_counter(ctx.remaining_accounts, &[&ctx.accounts.payee], |pot| vec![pot])?;
+        let amount = escrow.deposited;
+        if escrow.is_funded() {
+            vault.pay_winner(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), amount)?;
+            vault.pay_legs(legs, &[&ctx.accounts.payer], |pot| vec![pot])?;
+            // an unfunded payer may never have opened the vault, so there's nothing to close
+            vault.close_vault()?;
+        }
+        let counter = escrow.counter.as_ref().ok_or(error!(ErrorCode::NotASwap))?;
+        emit!(SwapSettled {
+            address: escrow.key(),
+            executed: false,
+            judged: false,
             amount,
-        )?;
-        emit!(EscrowReleased {
+            fees_paid: 0,
+            counter_amount: counter.deposited,
+            counter_fees_paid: 0,
+            token_mint: escrow.token_mint,
+            counter_mint: counter.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.recoveries += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Recovered);
+        Ok(())
+    }
+
+    // true executes the trade, false unwinds it. either way both sides pay the judge fee on what they get - the main
+    // asset clamped by the escrow's fee bounds like any ruling, the counter and asset legs plain bps.
+    // remaining accounts: the counter ATA, then judge's, treasury's and the counter recipient's token accounts for
+    // the counter mint (skipped if the payee never deposited), then [leg ATA, judge, treasury, recipient] per asset leg
+    pub fn judge_swap<'info>(ctx: Context<'_, '_, '_, 'info, JudgeSwapContext<'info>>, decision: bool) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        if decision && !(escrow.is_funded() && escrow.counter_funded()) {
+            return Err(error!(ErrorCode::SwapNotFunded));
+        }
+        if escrow.judge_window_closed(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::JudgeDeadlinePassed));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let (recipient, recipient_token_account, counter_recipient) = if decision {
+            (&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), &ctx.accounts.payer)
+        } else {
+            (&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), &ctx.accounts.payee)
+        };
+        let config = &ctx.accounts.config;
+        let split = |pot| {
+            let fee = escrow.fee_due(pot);
+            let (judge_paid, treasury_paid) = config.split_judge_fee(fee);
+            vec![judge_paid, treasury_paid, pot - fee]
+        };
+        let legs = vault.pay_counter(ctx.remaining_accounts, &[&ctx.accounts.judge, &ctx.accounts.treasury, counter_recipient], split)?;
+        let (mut amount, mut fee) = (0, 0);
+        if escrow.is_funded() {
+            fee = escrow.judge_fee(escrow.deposited);
+            amount = escrow.deposited - fee;
+            let (judge_paid, treasury_paid) = config.split_judge_fee(fee);
+            vault.pay(&ctx.accounts.judge, ctx.accounts.judge_token_account.as_deref(), judge_paid)?;
+            vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), treasury_paid)?;
+            vault.pay_winner(recipient, recipient_token_account, amount)?;
+            vault.pay_legs(legs, &[&ctx.accounts.judge, &ctx.accounts.treasury, recipient], split)?;
+            vault.close_vault()?;
+        }
+        let counter = escrow.counter.as_ref().ok_or(error!(ErrorCode::NotASwap))?;
+        let counter_fee = escrow.fee_due(counter.deposited);
+        emit!(SwapSettled {
+            address: escrow.key(),
+            executed: decision,
+            judged: true,
+            amount,
+            fees_paid: fee,
+            counter_amount: counter.deposited - counter_fee,
+            counter_fees_paid: counter_fee,
+            token_mint: escrow.token_mint,
+            counter_mint: counter.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.record_ruling(!decision);
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.record_ruling(decision);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Judged { for_payee: decision, fee });
+        Ok(())
+    }
+
+    pub fn set_vesting(ctx: Context<SetVestingContext>, steps: u16) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        if escrow.is_wrapped_sol() {
+            return Err(error!(ErrorCode::WrappedSolPartialPayout));
+        }
+        if !escrow.legs.is_empty() || escrow.is_swap() || escrow.nft {
+            return Err(error!(ErrorCode::VestingIncompatible));
+        }
+        if escrow.recurring.is_some() {
+            return Err(error!(ErrorCode::RecurringIncompatible));
+        }
+        if !escrow.payees.is_empty() {
+            return Err(error!(ErrorCode::SplitIncompatible));
+        }
+        escrow.vesting = Some(Vesting { steps, claimed: 0, frozen_at: 0 });
+        emit!(VestingSet {
+            address: escrow.key(),
+            steps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVestedContext>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let amount = escrow.vested_unclaimed(Clock::get()?.unix_timestamp);
+        if amount == 0 {
+            return Err(error!(ErrorCode::NothingVested));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let tax = escrow.tax_due(amount);
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), tax)?;
+        vault.pay(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), amount - tax)?;
+        // the last claim empties the escrow, so it closes like any other settlement
+        let done = amount == escrow.deposited;
+        if done {
+            vault.close_vault()?;
+        }
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.deposited -= amount;
+        let claimed = escrow.vesting.as_mut().map_or(0, |vesting| {
+            vesting.claimed += amount;
+            vesting.claimed
+        });
+        emit!(VestedClaimed {
+            address: escrow.key(),
+            amount: amount - tax,
+            tax_paid: tax,
+            claimed,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        // every claim is money that settled, the escrow only counts as completed once it's all out
+        if let Some(volume) = ctx.accounts.payer_volume.as_mut() {
+            volume.volume = volume.volume.saturating_add(amount);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Released { tax });
+        if done {
+            if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+                reputation.completed_as_payer += 1;
+            }
+            if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+                reputation.completed_as_payee += 1;
+            }
+            escrow.close(ctx.accounts.payer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn set_recurring(ctx: Context<SetRecurringContext>, periods: u16, interval: i64, auto_release: bool) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        if escrow.is_wrapped_sol() {
+            return Err(error!(ErrorCode::WrappedSolPartialPayout));
+        }
+        if !escrow.legs.is_empty() || escrow.is_swap() || escrow.nft || escrow.vesting.is_some() {
+            return Err(error!(ErrorCode::RecurringIncompatible));
+        }
+        if !escrow.payees.is_empty() {
+            return Err(error!(ErrorCode::SplitIncompatible));
+        }
+        if periods == 0 || interval <= 0 {
+            return Err(error!(ErrorCode::InvalidRecurrence));
+        }
+        let span = interval.checked_mul(periods as i64).ok_or(error!(ErrorCode::InvalidRecurrence))?;
+        escrow.recurring = Some(Recurring { periods, interval, released: 0, auto_release });
+        // the escrow lives as long as its periods, recover waits out the judge's window after the last one.
+        // the judge doesn't have to wait that long though, see dispute_opens
+        escrow.deadline = escrow.creation_time + span;
+        escrow.judge_deadline = escrow.deadline + JUDGE_WINDOW;
+        emit!(RecurringSet {
+            address: escrow.key(),
+            periods,
+            interval,
+            auto_release,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn release_period(ctx: Context<ReleasePeriodContext>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let auto_release = escrow.recurring.as_ref().is_some_and(|recurring| recurring.auto_release);
+        // the payer can approve a period whenever they like, everyone else waits for it to unlock
+        if ctx.accounts.user.key() != escrow.payer && !(auto_release && Clock::get()?.unix_timestamp >= escrow.next_period_unlocks()) {
+            return Err(error!(ErrorCode::PeriodLocked));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pot = escrow.next_period_amount();
+        let tax = escrow.tax_due(pot);
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), tax)?;
+        vault.pay(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), pot - tax)?;
+        let escrow = &mut ctx.accounts.escrow;
+        let done = escrow.record_period(pot);
+        emit!(PeriodReleased {
+            address: escrow.key(),
+            period: escrow.recurring.as_ref().map_or(0, |recurring| recurring.released),
+            amount: pot - tax,
+            tax_paid: tax,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        // every period is money that settled, the escrow only counts as completed after the last one
+        if let Some(volume) = ctx.accounts.payer_volume.as_mut() {
+            volume.volume = volume.volume.saturating_add(pot);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Released { tax });
+        if done {
+            if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+                reputation.completed_as_payer += 1;
+            }
+            if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+                reputation.completed_as_payee += 1;
+            }
+            Vault {
+                escrow,
+                escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            }
+            .close_vault()?;
+            escrow.close(ctx.accounts.payer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // rules on the disputed period only. the rest of the schedule carries on afterwards
+    pub fn judge_period(ctx: Context<JudgePeriodContext>, decision: bool) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        if escrow.judge_window_closed(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::JudgeDeadlinePassed));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pot = escrow.next_period_amount();
+        let fee = escrow.judge_fee(pot);
+        let (judge_paid, treasury_paid) = ctx.accounts.config.split_judge_fee(fee);
+        let amount = pot - fee;
+        vault.pay(&ctx.accounts.judge, ctx.accounts.judge_token_account.as_deref(), judge_paid)?;
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), treasury_paid)?;
+        let winner = if decision {
+            vault.pay(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), amount)?;
+            escrow.payee
+        } else {
+            vault.pay(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), amount)?;
+            escrow.payer
+        };
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.disputed = false;
+        let done = escrow.record_period(pot);
+        emit!(EscrowJudged {
+            address: escrow.key(),
+            winner,
+            amount_awarded: amount,
+            fee_collected: fee,
+            fee_bps: escrow.fee,
+            judge_paid,
+            treasury_paid,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.record_ruling(!decision);
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.record_ruling(decision);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Judged { for_payee: decision, fee });
+        if done {
+            Vault {
+                escrow,
+                escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            }
+            .close_vault()?;
+            escrow.close(ctx.accounts.payer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // stops the schedule. with auto release the payee still gets every period that already unlocked, the payer gets
+    // the rest back untaxed
+    pub fn cancel_recurring(ctx: Context<CancelRecurringContext>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &ctx.accounts.escrow;
+        let owed = escrow.unlocked_periods_amount(now);
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let tax = escrow.tax_due(owed);
+        let refunded = escrow.deposited - owed;
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), tax)?;
+        vault.pay(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), owed - tax)?;
+        vault.pay_winner(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), refunded)?;
+        vault.close_vault()?;
+        emit!(RecurringCancelled {
+            address: escrow.key(),
+            paid_to_payee: owed - tax,
+            tax_paid: tax,
+            refunded,
+            token_mint: escrow.token_mint,
+            timestamp: now,
+        });
+        // the periods the payee was still owed settled, the rest is a refund. cutting a schedule short isn't a
+        // completed escrow for either side
+        if let Some(volume) = ctx.accounts.payer_volume.as_mut() {
+            volume.volume = volume.volume.saturating_add(owed);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Returned { tax });
+        Ok(())
+    }
+
+    pub fn set_payees(ctx: Context<SetPayeesContext>, payees: Vec<PayeeShareArgs>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        if escrow.is_wrapped_sol() {
+            return Err(error!(ErrorCode::WrappedSolPartialPayout));
+        }
+        if !escrow.legs.is_empty() || escrow.is_swap() || escrow.nft || escrow.vesting.is_some() || escrow.recurring.is_some() {
+            return Err(error!(ErrorCode::SplitIncompatible));
+        }
+        if payees.len() < 2 || payees.len() > MAX_PAYEES {
+            return Err(error!(ErrorCode::InvalidPayeeShares));
+        }
+        if payees.iter().any(|share| share.bps == 0) || payees.iter().map(|share| share.bps as u32).sum::<u32>() != 10000 {
+            return Err(error!(ErrorCode::InvalidPayeeShares));
+        }
+        if payees.iter().enumerate().any(|(i, share)| payees[..i].iter().any(|other| other.payee == share.payee)) {
+            return Err(error!(ErrorCode::InvalidPayeeShares));
+        }
+        // the escrow's payee stays the one the single-payee instructions check against
+        if !payees.iter().any(|share| share.payee == escrow.payee) {
+            return Err(error!(ErrorCode::InvalidPayeeShares));
+        }
+        escrow.payees = payees
+            .iter()
+            .map(|share| PayeeShare { payee: share.payee, bps: share.bps, disputed: false, settled: false })
+            .collect();
+        let approver = escrow.approver;
+        escrow.set_approver(approver)?;
+        emit!(PayeesSet {
             address: escrow.key(),
-            amount: amount,
+            payees: payees.iter().map(|share| share.payee).collect(),
+            shares: payees.iter().map(|share| share.bps).collect(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn dispute_share(ctx: Context<DisputeShareContext>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let payee = ctx.accounts.payee.key();
+        let Some(share) = escrow.payees.iter_mut().find(|share| share.payee == payee) else {
+            return Err(error!(ErrorCode::NotAPayee));
+        };
+        if share.settled {
+            return Err(error!(ErrorCode::ShareSettled));
+        }
+        if share.disputed {
+            return Err(error!(ErrorCode::ShareDisputed));
+        }
+        share.disputed = true;
+        emit!(EscrowDisputed {
+            address: escrow.key(),
+            payer: escrow.payer,
+            payee,
+            disputed_by: payee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.disputer_reputation.as_mut() {
+            reputation.disputes_raised += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Disputed);
+        Ok(())
+    }
+
+    // one payee hands their own share back to the payer, the rest of the split carries on
+    pub fn return_share(ctx: Context<ReturnShareContext>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let payee = ctx.accounts.payee.key();
+        let Some(share) = escrow.payees.iter().find(|share| share.payee == payee) else {
+            return Err(error!(ErrorCode::NotAPayee));
+        };
+        if share.settled {
+            return Err(error!(ErrorCode::ShareSettled));
+        }
+        if share.disputed {
+            return Err(error!(ErrorCode::ShareDisputed));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pot = escrow.share_of(payee);
+        let fee = escrow.tax_due(pot);
+        let amount = pot - fee;
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), fee)?;
+        vault.pay(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), amount)?;
+        let escrow = &mut ctx.accounts.escrow;
+        let done = escrow.settle_share(payee, pot);
+        emit!(EscrowReturned {
+            address: escrow.key(),
+            amount,
             tax_paid: fee,
             token_mint: escrow.token_mint,
             timestamp: Clock::get()?.unix_timestamp,
         });
+        // each payee is done with their share, the payer only once every share has settled
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.completed_as_payee += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Returned { tax: fee });
+        if done {
+            if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+                reputation.completed_as_payer += 1;
+            }
+            Vault {
+                escrow,
+                escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            }
+            .close_vault()?;
+            escrow.close(ctx.accounts.payer.to_account_info())?;
+        }
         Ok(())
     }
-    pub fn return_token_escrow(ctx: Context<ReturnTokenContext>) -> Result<()> {
+
+    // rules on one payee's disputed share, the rest of the split carries on
+    pub fn judge_share(ctx: Context<JudgeShareContext>, decision: bool) -> Result<()> {
         let escrow = &ctx.accounts.escrow;
-        // calculate the tax
-        let init_amount = escrow.amount;
-        let fee = (init_amount * (escrow.tax as u64)) / 10000; // basis point tax for being available for judgement
-        let amount = init_amount - fee;
-        let escrow_token_account = &mut ctx.accounts.escrow_token_account;
-        let payer_token_account = &mut ctx.accounts.payer_token_account;
-        let treasury_token_account = &mut ctx.accounts.treasury_token_account;
-        transfer(CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: escrow_token_account.to_account_info(),
-                to: treasury_token_account.to_account_info(),
-                authority: escrow.to_account_info(),
-            },
-        ),
-        fee,
-        )?;
-        transfer(
+        let payee = ctx.accounts.payee.key();
+        if !escrow.payees.iter().any(|share| share.payee == payee && share.disputed && !share.settled) {
+            return Err(error!(ErrorCode::ShareNotDisputed));
+        }
+        if escrow.judge_window_closed(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::JudgeDeadlinePassed));
+        }
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pot = escrow.share_of(payee);
+        let fee = escrow.judge_fee(pot);
+        let (judge_paid, treasury_paid) = ctx.accounts.config.split_judge_fee(fee);
+        let amount = pot - fee;
+        vault.pay(&ctx.accounts.judge, ctx.accounts.judge_token_account.as_deref(), judge_paid)?;
+        vault.pay(&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_deref(), treasury_paid)?;
+        let winner = if decision {
+            vault.pay(&ctx.accounts.payee, ctx.accounts.payee_token_account.as_deref(), amount)?;
+            payee
+        } else {
+            vault.pay(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_deref(), amount)?;
+            escrow.payer
+        };
+        let escrow = &mut ctx.accounts.escrow;
+        let done = escrow.settle_share(payee, pot);
+        emit!(EscrowJudged {
+            address: escrow.key(),
+            winner,
+            amount_awarded: amount,
+            fee_collected: fee,
+            fee_bps: escrow.fee,
+            judge_paid,
+            treasury_paid,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.record_ruling(!decision);
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.record_ruling(decision);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Judged { for_payee: decision, fee });
+        if done {
+            Vault {
+                escrow,
+                escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+                token_program: ctx.accounts.token_program.as_ref(),
+            }
+            .close_vault()?;
+            escrow.close(ctx.accounts.payer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn dispute_escrow(ctx: Context<DisputeEscrowContext>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let user = &ctx.accounts.user;
+        let config = &ctx.accounts.config;
+        if !escrow.payees.is_empty() && user.key() != escrow.payer && user.key() != config.judge {
+            return Err(error!(ErrorCode::SplitEscrow));
+        }
+        // Judges can't get involved until after the deadline
+        if user.key() == config.judge {
+            let now = Clock::get()?.unix_timestamp;
+            if now <= escrow.dispute_opens() {
+                return Err(error!(ErrorCode::UninvolvedUser));
+            }
+        }
+        emit!(EscrowDisputed {
+            address: escrow.key(),
+            payer: escrow.payer,
+            payee: escrow.payee,
+            disputed_by: user.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        escrow.disputed = true;
+        if let Some(vesting) = &mut escrow.vesting {
+            vesting.frozen_at = Clock::get()?.unix_timestamp;
+        }
+        if let Some(reputation) = ctx.accounts.disputer_reputation.as_mut() {
+            reputation.disputes_raised += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Disputed);
+        Ok(())
+    }
+
+    pub fn deposit_sol_funds<'info>(ctx: Context<'_, '_, '_, 'info, DepositSolanaContext<'info>>) -> Result<()> {
+        if ctx.accounts.escrow.swap_expired(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::SwapExpired));
+        }
+        // the payer is owned by the system program, so it has to move the lamports. rent was paid at creation
+        anchor_lang::system_program::transfer(
             CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: escrow_token_account.to_account_info(),
-                    to: payer_token_account.to_account_info(),
-                    authority: escrow.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
                 },
             ),
-            amount,
+            ctx.accounts.escrow.amount,
         )?;
-        emit!(EscrowReturned {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.deposited = escrow.amount;
+        emit!(EscrowDeposited {
             address: escrow.key(),
-            amount: amount,
-            tax_paid: fee,
+            amount: escrow.deposited,
             token_mint: escrow.token_mint,
             timestamp: Clock::get()?.unix_timestamp,
         });
+        deposit_legs(escrow, &ctx.accounts.payer, ctx.accounts.token_program.as_ref(), ctx.remaining_accounts)?;
         Ok(())
     }
 
-    pub fn recover_token_funds(ctx: Context<RecoverTokenContext>) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+    pub fn deposit_token_funds<'info>(ctx: Context<'_, '_, '_, 'info, DepositTokenContext<'info>>) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
-        if now <= escrow.judge_deadline {
-            return Err(error!(ErrorCode::RecoverTooEarly));
+        if escrow.swap_expired(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::SwapExpired));
         }
         if let Some(_token_mint_pubkey) = escrow.token_mint {
-            let payer_token_account = &mut ctx.accounts.payer_token_account;
-            let escrow_token_account = &mut ctx.accounts.escrow_token_account;
+            let payer = &mut ctx.accounts.payer;
+            let payer_token_account = &ctx.accounts.payer_token_account;
+            let escrow_token_account = &ctx.accounts.escrow_token_account;
             transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: escrow_token_account.to_account_info(),
-                        to: payer_token_account.to_account_info(),
-                        authority: escrow.to_account_info(),
-                    },
-                ),
-                escrow.amount
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                from: payer_token_account.to_account_info(),
+                to: escrow_token_account.to_account_info(),
+                authority: payer.to_account_info(),
+                },
+            ),
+            escrow.amount,
             )?;
-            emit!(EscrowRecovered {
-                address: escrow.key(),
-                amount: escrow.amount,
-                token_mint: escrow.token_mint,
-                timestamp: Clock::get()?.unix_timestamp,
+            escrow.deposited = escrow.amount;
+            emit!(EscrowDeposited {
+            address: escrow.key(),
+            amount: escrow.amount,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
             });
+            deposit_legs(escrow, &ctx.accounts.payer, Some(&ctx.accounts.token_program), ctx.remaining_accounts)?;
+        } else {
+            return Err(error!(ErrorCode::EscrowNotToken))
         }
         Ok(())
     }
-}
-
-//  ========================================================================================================  //
-//  Account Contexts                                                                                          //
-//    ▄████████  ▄████████  ▄████████     ███           ▄████████     ███     ▀████    ▐████▀    ▄████████    //
-//    ███    ███ ███    ███ ███    ███ ▀█████████▄      ███    ███ ▀█████████▄   ███▌   ████▀    ███    ███   //
-//    ███    ███ ███    █▀  ███    █▀     ▀███▀▀██      ███    █▀     ▀███▀▀██    ███  ▐███      ███    █▀    //
-//    ███    ███ ███        ███            ███   ▀      ███            ███   ▀    ▀███▄███▀      ███          //
-//  ▀███████████ ███        ███            ███          ███            ███        ████▀██▄     ▀███████████   //
-//    ███    ███ ███    █▄  ███    █▄      ███          ███    █▄      ███       ▐███  ▀███             ███   //
-//    ███    ███ ███    ███ ███    ███     ███          ███    ███     ███      ▄███     ███▄     ▄█    ███   //
-//    ███    █▀  ████████▀  ████████▀     ▄████▀        ████████▀     ▄████▀   ████       ███▄  ▄████████▀    //
-//  ========================================================================================================  //                                                               
 
+    pub fn deposit_wrapped_sol<'info>(ctx: Context<'_, '_, '_, 'info, DepositWrappedSolContext<'info>>) -> Result<()> {
+        if ctx.accounts.escrow.swap_expired(Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::SwapExpired));
+        }
+        // plain SOL in, the native mint's sync_native turns the lamports into a wSOL balance
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                },
+            ),
+            ctx.accounts.escrow.amount,
+        )?;
+        sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.escrow_token_account.to_account_info(),
+            },
+        ))?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.deposited = escrow.amount;
+        emit!(EscrowDeposited {
+            address: escrow.key(),
+            amount: escrow.deposited,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        deposit_legs(escrow, &ctx.accounts.payer, Some(&ctx.accounts.token_program), ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn increase_amount(ctx: Context<IncreaseAmountContext>, additional: u64) -> Result<()> {
+        let old_amount = ctx.accounts.escrow.top_up(additional)?;
+        let escrow = &ctx.accounts.escrow;
+        match escrow.token_mint {
+            None => {
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.payer.to_account_info(),
+                            to: escrow.to_account_info(),
+                        },
+                    ),
+                    additional,
+                )?;
+            }
+            Some(_) => {
+                let (Some(payer_token_account), Some(escrow_token_account), Some(token_program)) = (
+                    &ctx.accounts.payer_token_account,
+                    &ctx.accounts.escrow_token_account,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return Err(error!(ErrorCode::MissingTokenAccounts));
+                };
+                transfer(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        Transfer {
+                            from: payer_token_account.to_account_info(),
+                            to: escrow_token_account.to_account_info(),
+                            authority: ctx.accounts.payer.to_account_info(),
+                        },
+                    ),
+                    additional,
+                )?;
+            }
+        }
+        emit!(EscrowAmended {
+            address: escrow.key(),
+            old_amount,
+            new_amount: escrow.amount,
+            refunded: 0,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn amend_amount(ctx: Context<AmendAmountContext>, new_amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        if new_amount >= escrow.amount {
+            return Err(error!(ErrorCode::InvalidAmendment));
+        }
+        EscrowAccount::check_amount(new_amount, escrow.tax)?;
+        // an unfunded escrow just gets a new number, a funded one hands the difference back
+        let refunded = if escrow.is_funded() { escrow.amend_refund(new_amount) } else { 0 };
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        vault.pay(&ctx.accounts.payer, ctx.accounts.payer_token_account.as_ref(), refunded)?;
+        let escrow = &mut ctx.accounts.escrow;
+        let old_amount = escrow.amount;
+        escrow.amount = new_amount;
+        escrow.deposited -= refunded;
+        emit!(EscrowAmended {
+            address: escrow.key(),
+            old_amount,
+            new_amount,
+            refunded,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn release<'info>(ctx: Context<'_, '_, '_, 'info, ReleaseContext<'info>>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let vault = Vault {
+            escrow: &accounts.escrow,
+            escrow_token_account: accounts.escrow_token_account.as_deref(),
+            token_program: Some(&accounts.token_program),
+        };
+        let treasury = Party { wallet: &accounts.treasury, token_account: accounts.treasury_token_account.as_deref() };
+        let payee = Party { wallet: &accounts.payee, token_account: accounts.payee_token_account.as_deref() };
+        let ledger = ReleaseLedger {
+            payer_volume: &mut accounts.payer_volume,
+            payer_reputation: &mut accounts.payer_reputation,
+            payee_reputation: &mut accounts.payee_reputation,
+            global_stats: &mut accounts.global_stats,
+            mint_stats: &mut accounts.mint_stats,
+        };
+        release_escrow(&vault, treasury, payee, ctx.remaining_accounts, ledger)
+    }
+
+    // release, signed by the escrow's approver instead of the payer. pays out exactly like release
+    pub fn approve_release<'info>(ctx: Context<'_, '_, '_, 'info, ApproveReleaseContext<'info>>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let vault = Vault {
+            escrow: &accounts.escrow,
+            escrow_token_account: accounts.escrow_token_account.as_deref(),
+            token_program: Some(&accounts.token_program),
+        };
+        let treasury = Party { wallet: &accounts.treasury, token_account: accounts.treasury_token_account.as_deref() };
+        let payee = Party { wallet: &accounts.payee, token_account: accounts.payee_token_account.as_deref() };
+        let ledger = ReleaseLedger {
+            payer_volume: &mut accounts.payer_volume,
+            payer_reputation: &mut accounts.payer_reputation,
+            payee_reputation: &mut accounts.payee_reputation,
+            global_stats: &mut accounts.global_stats,
+            mint_stats: &mut accounts.mint_stats,
+        };
+        release_escrow(&vault, treasury, payee, ctx.remaining_accounts, ledger)
+    }
+
+    pub fn set_approver(ctx: Context<SetApproverContext>, approver: Option<Pubkey>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        let old_approver = escrow.approver;
+        escrow.set_approver(approver)?;
+        emit!(ApproverSet {
+            address: escrow.key(),
+            old_approver,
+            new_approver: approver,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn return_funds<'info>(ctx: Context<'_, '_, '_, 'info, ReturnContext<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let treasury = Party { wallet: &ctx.accounts.treasury, token_account: ctx.accounts.treasury_token_account.as_deref() };
+        let payer = Party { wallet: &ctx.accounts.payer, token_account: ctx.accounts.payer_token_account.as_deref() };
+        let (amount, fee) = vault.return_funds(treasury, payer, ctx.remaining_accounts)?;
+        emit!(EscrowReturned {
+            address: escrow.key(),
+            amount,
+            tax_paid: fee,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.completed_as_payer += 1;
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.completed_as_payee += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Returned { tax: fee });
+        Ok(())
+    }
+
+    pub fn judge<'info>(ctx: Context<'_, '_, '_, 'info, JudgeContext<'info>>, decision: bool) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let parties = Parties {
+            payer: Party { wallet: &ctx.accounts.payer, token_account: ctx.accounts.payer_token_account.as_deref() },
+            payee: Party { wallet: &ctx.accounts.payee, token_account: ctx.accounts.payee_token_account.as_deref() },
+            treasury: Party { wallet: &ctx.accounts.treasury, token_account: ctx.accounts.treasury_token_account.as_deref() },
+        };
+        let judge = Party { wallet: &ctx.accounts.judge, token_account: ctx.accounts.judge_token_account.as_deref() };
+        let Ruling { winner, amount, fee, judge_paid, treasury_paid } =
+            vault.judge(&ctx.accounts.config, judge, &parties, decision, ctx.remaining_accounts)?;
+        emit!(EscrowJudged {
+            address: escrow.key(),
+            winner,
+            amount_awarded: amount,
+            fee_collected: fee,
+            fee_bps: escrow.fee,
+            judge_paid,
+            treasury_paid,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.record_ruling(!decision);
+        }
+        if let Some(reputation) = ctx.accounts.payee_reputation.as_mut() {
+            reputation.record_ruling(decision);
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Judged { for_payee: decision, fee });
+        Ok(())
+    }
+
+    pub fn recover<'info>(ctx: Context<'_, '_, '_, 'info, RecoverContext<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let vault = Vault {
+            escrow,
+            escrow_token_account: ctx.accounts.escrow_token_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let treasury = Party { wallet: &ctx.accounts.treasury, token_account: ctx.accounts.treasury_token_account.as_deref() };
+        let payer = Party { wallet: &ctx.accounts.payer, token_account: ctx.accounts.payer_token_account.as_deref() };
+        let payee = Party { wallet: &ctx.accounts.payee, token_account: ctx.accounts.payee_token_account.as_deref() };
+        let amount = vault.recover(treasury, payer, payee, ctx.remaining_accounts)?;
+        emit!(EscrowRecovered {
+            address: escrow.key(),
+            amount,
+            token_mint: escrow.token_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(reputation) = ctx.accounts.payer_reputation.as_mut() {
+            reputation.recoveries += 1;
+        }
+        record_stats(&mut ctx.accounts.global_stats, &mut ctx.accounts.mint_stats, StatsUpdate::Recovered);
+        Ok(())
+    }
+}
+
+// ========================================================================= //
+// Payouts                                                                   //
+//                                                                           //
+// Every settlement path pays through a Vault, so SOL and token escrows      //
+// share the same checks. SOL sits as lamports on the escrow PDA, tokens in  //
+// the escrow's token account, signed for with the escrow seeds. wSOL        //
+// escrows settle as tokens but unwrap the winner's cut to native SOL.       //
+// ========================================================================= //
+
+pub struct Vault<'a, 'info> {
+    pub escrow: &'a Account<'info, EscrowAccount>,
+    pub escrow_token_account: Option<&'a Account<'info, TokenAccount>>,
+    pub token_program: Option<&'a Program<'info, Token>>,
+}
+
+// a wallet and, for token escrows, its token account for the escrow's mint
+#[derive(Clone, Copy)]
+pub struct Party<'a, 'info> {
+    pub wallet: &'a AccountInfo<'info>,
+    pub token_account: Option<&'a Account<'info, TokenAccount>>,
+}
+
+// everyone a ruling can pay besides the judge
+pub struct Parties<'a, 'info> {
+    pub payer: Party<'a, 'info>,
+    pub payee: Party<'a, 'info>,
+    pub treasury: Party<'a, 'info>,
+}
+
+// what a ruling paid out, for EscrowJudged
+pub struct Ruling {
+    pub winner: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub judge_paid: u64,
+    pub treasury_paid: u64,
+}
+
+impl<'a, 'info> Vault<'a, 'info> {
+    // tax to the treasury, the rest to the payee (shared by bps on split escrows), legs the same way.
+    // returns (paid out, tax)
+    pub fn release(&self, treasury: Party<'_, 'info>, payee: Party<'_, 'info>, remaining: &[AccountInfo<'info>]) -> Result<(u64, u64)> {
+        let escrow = self.escrow;
+        if escrow.has_disputed_share() {
+            return Err(error!(ErrorCode::ShareDisputed));
+        }
+        let pot = escrow.deposited;
+        let fee = escrow.tax_due(pot);
+        let amount = pot - fee;
+        self.pay(treasury.wallet, treasury.token_account, fee)?;
+        if escrow.payees.is_empty() {
+            self.pay_winner(payee.wallet, payee.token_account, amount)?;
+        } else {
+            self.pay_shares(remaining, amount)?;
+        }
+        self.pay_legs(remaining, &[treasury.wallet, payee.wallet], |pot| {
+            let tax = escrow.tax_due(pot);
+            vec![tax, pot - tax]
+        })?;
+        self.close_vault()?;
+        Ok((amount, fee))
+    }
+
+    // same as release, except everything goes back to the payer. returns (paid out, tax)
+    pub fn return_funds(&self, treasury: Party<'_, 'info>, payer: Party<'_, 'info>, remaining: &[AccountInfo<'info>]) -> Result<(u64, u64)> {
+        let escrow = self.escrow;
+        let pot = escrow.deposited;
+        let fee = escrow.tax_due(pot);
+        let amount = pot - fee;
+        self.pay(treasury.wallet, treasury.token_account, fee)?;
+        self.pay_winner(payer.wallet, payer.token_account, amount)?;
+        self.pay_legs(remaining, &[treasury.wallet, payer.wallet], |pot| {
+            let tax = escrow.tax_due(pot);
+            vec![tax, pot - tax]
+        })?;
+        self.close_vault()?;
+        Ok((amount, fee))
+    }
+
+    // the judge fee off the top, split between judge and treasury, the rest to whoever won. a vesting payee keeps
+    // whatever had unlocked when the dispute froze the schedule, the judge rules on the rest
+    pub fn judge(
+        &self,
+        config: &ConfigAccount,
+        judge: Party<'_, 'info>,
+        parties: &Parties<'_, 'info>,
+        decision: bool,
+        remaining: &[AccountInfo<'info>],
+    ) -> Result<Ruling> {
+        let escrow = self.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        if escrow.judge_window_closed(now) {
+            return Err(error!(ErrorCode::JudgeDeadlinePassed));
+        }
+        let vested = escrow.vested_unclaimed(now);
+        let vested_tax = escrow.tax_due(vested);
+        self.pay(parties.treasury.wallet, parties.treasury.token_account, vested_tax)?;
+        self.pay(parties.payee.wallet, parties.payee.token_account, vested - vested_tax)?;
+        let pot = escrow.deposited - vested;
+        let fee = escrow.judge_fee(pot);
+        let (judge_paid, treasury_paid) = config.split_judge_fee(fee);
+        let amount = pot - fee;
+        self.pay(judge.wallet, judge.token_account, judge_paid)?;
+        self.pay(parties.treasury.wallet, parties.treasury.token_account, treasury_paid)?;
+        let winner = if decision { parties.payee } else { parties.payer };
+        if decision && !escrow.payees.is_empty() {
+            self.pay_shares(remaining, amount)?;
+        } else {
+            self.pay_winner(winner.wallet, winner.token_account, amount)?;
+        }
+        self.pay_legs(remaining, &[judge.wallet, parties.treasury.wallet, winner.wallet], |pot| {
+            let fee = escrow.fee_due(pot);
+            let (judge_paid, treasury_paid) = config.split_judge_fee(fee);
+            vec![judge_paid, treasury_paid, pot - fee]
+        })?;
+        self.close_vault()?;
+        Ok(Ruling { winner: winner.wallet.key(), amount, fee, judge_paid, treasury_paid })
+    }
+
+    // everything back to the payer, untaxed, once the judge deadline has passed with nobody acting. whatever vested
+    // is still the payee's and goes to them first, taxed like a claim. returns what the payer got
+    pub fn recover(
+        &self,
+        treasury: Party<'_, 'info>,
+        payer: Party<'_, 'info>,
+        payee: Party<'_, 'info>,
+        remaining: &[AccountInfo<'info>],
+    ) -> Result<u64> {
+        let escrow = self.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        if now <= escrow.judge_deadline {
+            return Err(error!(ErrorCode::RecoverTooEarly));
+        }
+        let vested = escrow.vested_unclaimed(now);
+        let vested_tax = escrow.tax_due(vested);
+        self.pay(treasury.wallet, treasury.token_account, vested_tax)?;
+        self.pay(payee.wallet, payee.token_account, vested - vested_tax)?;
+        let amount = escrow.deposited - vested;
+        self.pay_winner(payer.wallet, payer.token_account, amount)?;
+        self.pay_legs(remaining, &[payer.wallet], |pot| vec![pot])?;
+        self.close_vault()?;
+        Ok(amount)
+    }
+
+    // fees and taxes - lamports to the wallet for SOL escrows, tokens to the wallet's token account otherwise
+    pub fn pay(&self, wallet: &AccountInfo<'info>, token_account: Option<&Account<'info, TokenAccount>>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let Some(mint) = self.escrow.token_mint else {
+            return self.pay_lamports(wallet, amount);
+        };
+        let (escrow_token_account, token_program) = self.token_accounts()?;
+        let Some(token_account) = token_account else {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        };
+        if token_account.mint != mint {
+            return Err(error!(ErrorCode::WrongToken));
+        }
+        if token_account.owner != wallet.key() {
+            return Err(error!(ErrorCode::UninvolvedUser));
+        }
+        let payer_key = self.escrow.payer;
+        let seeds: &[&[u8]] = &[b"escrow", payer_key.as_ref(), &[self.escrow.bump]];
+        transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: escrow_token_account.to_account_info(),
+                    to: token_account.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )
+    }
+
+    // the escrowed funds themselves. same as pay, except wSOL gets unwrapped: the vault is closed into the
+    // escrow PDA and the winner is paid in lamports. both rents stay behind for the payer when the escrow closes
+    pub fn pay_winner(&self, wallet: &AccountInfo<'info>, token_account: Option<&Account<'info, TokenAccount>>, amount: u64) -> Result<()> {
+        if !self.escrow.is_wrapped_sol() {
+            return self.pay(wallet, token_account, amount);
+        }
+        let (escrow_token_account, token_program) = self.token_accounts()?;
+        let payer_key = self.escrow.payer;
+        let seeds: &[&[u8]] = &[b"escrow", payer_key.as_ref(), &[self.escrow.bump]];
+        close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: escrow_token_account.to_account_info(),
+                destination: self.escrow.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+        self.pay_lamports(wallet, amount)
+    }
+
+    // split escrows: the payee side shared out by bps among the payees still in the split. remaining accounts hold
+    // one account per open share, in order - the payee's wallet for SOL, their token account otherwise
+    pub fn pay_shares(&self, remaining: &[AccountInfo<'info>], amount: u64) -> Result<()> {
+        let cuts = self.escrow.split_among_payees(amount);
+        if remaining.len() < cuts.len() {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        }
+        let payer_key = self.escrow.payer;
+        let seeds: &[&[u8]] = &[b"escrow", payer_key.as_ref(), &[self.escrow.bump]];
+        for ((payee, cut), account) in cuts.into_iter().zip(remaining) {
+            let Some(mint) = self.escrow.token_mint else {
+                if account.key() != payee {
+                    return Err(error!(ErrorCode::UninvolvedUser));
+                }
+                self.pay_lamports(account, cut)?;
+                continue;
+            };
+            if cut == 0 {
+                continue;
+            }
+            let (escrow_token_account, token_program) = self.token_accounts()?;
+            check_token_account(account, mint, payee)?;
+            transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_token_account.to_account_info(),
+                        to: account.clone(),
+                        authority: self.escrow.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                cut,
+            )?;
+        }
+        Ok(())
+    }
+
+    // every extra asset leg, split the same way as the main asset. remaining accounts hold, per leg, the escrow's
+    // ATA for that mint followed by one token account per wallet (in the same order as wallets)
+    pub fn pay_legs(&self, remaining: &[AccountInfo<'info>], wallets: &[&AccountInfo<'info>], split: impl Fn(u64) -> Vec<u64>) -> Result<()> {
+        if self.escrow.legs.is_empty() {
+            return Ok(());
+        }
+        let stride = 1 + wallets.len();
+        if remaining.len() < self.escrow.legs.len() * stride {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        }
+        for (leg, accounts) in self.escrow.legs.iter().zip(remaining.chunks(stride)) {
+            self.pay_leg(leg, accounts, wallets, split(leg.deposited))?;
+        }
+        Ok(())
+    }
+
+    // a swap's counter leg, if the payee deposited one. it takes the first [counter ATA, wallets...] of the remaining
+    // accounts and hands back the rest for pay_legs
+    pub fn pay_counter<'r>(
+        &self,
+        remaining: &'r [AccountInfo<'info>],
+        wallets: &[&AccountInfo<'info>],
+        split: impl Fn(u64) -> Vec<u64>,
+    ) -> Result<&'r [AccountInfo<'info>]> {
+        let Some(counter) = self.escrow.counter.as_ref().filter(|counter| counter.deposited > 0) else {
+            return Ok(remaining);
+        };
+        let stride = 1 + wallets.len();
+        if remaining.len() < stride {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        }
+        self.pay_leg(counter, &remaining[..stride], wallets, split(counter.deposited))?;
+        Ok(&remaining[stride..])
+    }
+
+    fn pay_leg(&self, leg: &AssetLeg, accounts: &[AccountInfo<'info>], wallets: &[&AccountInfo<'info>], amounts: Vec<u64>) -> Result<()> {
+        let Some(token_program) = self.token_program else {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        };
+        let leg_vault = &accounts[0];
+        if leg_vault.key() != get_associated_token_address(&self.escrow.key(), &leg.mint) {
+            return Err(error!(ErrorCode::InvalidLegVault));
+        }
+        let payer_key = self.escrow.payer;
+        let seeds: &[&[u8]] = &[b"escrow", payer_key.as_ref(), &[self.escrow.bump]];
+        for ((wallet, token_account), amount) in wallets.iter().zip(&accounts[1..]).zip(amounts.iter().copied()) {
+            if amount == 0 {
+                continue;
+            }
+            // nobody's paying rent mid-settlement, so recipients bring their own token accounts
+            if token_account.data_is_empty() || token_account.owner != &anchor_spl::token::ID {
+                return Err(error!(ErrorCode::LegTokenAccountMissing));
+            }
+            check_token_account(token_account, leg.mint, wallet.key())?;
+            transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: leg_vault.clone(),
+                        to: token_account.clone(),
+                        authority: self.escrow.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+        self.close_if_empty(leg_vault, token_program)?;
+        // the last wallet is always whoever the leg is settling to, everyone before them took a cut
+        let (winner_amount, fees) = amounts.split_last().map_or((0, &[][..]), |(last, rest)| (*last, rest));
+        emit!(EscrowLegSettled {
+            address: self.escrow.key(),
+            mint: leg.mint,
+            recipient: wallets[wallets.len() - 1].key(),
+            amount: winner_amount,
+            fees_paid: fees.iter().sum(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    fn pay_lamports(&self, wallet: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let escrow_info = self.escrow.to_account_info();
+        let remaining = escrow_info.lamports().checked_sub(amount).ok_or(error!(ErrorCode::InsufficientFunds))?;
+        **escrow_info.try_borrow_mut_lamports()? = remaining;
+        **wallet.try_borrow_mut_lamports()? += amount;
+        Ok(())
+    }
+
+    fn token_accounts(&self) -> Result<(&'a Account<'info, TokenAccount>, &'a Program<'info, Token>)> {
+        let (Some(escrow_token_account), Some(token_program)) = (self.escrow_token_account, self.token_program) else {
+            return Err(error!(ErrorCode::MissingTokenAccounts));
+        };
+        if escrow_token_account.owner != self.escrow.key() || Some(escrow_token_account.mint) != self.escrow.token_mint {
+            return Err(error!(ErrorCode::WrongToken));
+        }
+        Ok((escrow_token_account, token_program))
+    }
+
+    // once the main asset is paid out, its token account goes too. wSOL vaults are usually gone already
+    pub fn close_vault(&self) -> Result<()> {
+        if self.escrow.token_mint.is_none() {
+            return Ok(());
+        }
+        let (escrow_token_account, token_program) = self.token_accounts()?;
+        self.close_if_empty(&escrow_token_account.to_account_info(), token_program)
+    }
+
+    // rent goes into the escrow PDA, which hands it to the payer when it closes. anything still holding
+    // tokens (say someone sent a few in after the fact) is left open rather than blocking the settlement
+    fn close_if_empty(&self, token_account: &AccountInfo<'info>, token_program: &Program<'info, Token>) -> Result<()> {
+        if token_account.lamports() == 0 {
+            return Ok(());
+        }
+        if TokenAccount::try_deserialize(&mut &token_account.try_borrow_data()?[..])?.amount > 0 {
+            return Ok(());
+        }
+        let payer_key = self.escrow.payer;
+        let seeds: &[&[u8]] = &[b"escrow", payer_key.as_ref(), &[self.escrow.bump]];
+        close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: token_account.clone(),
+                destination: self.escrow.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            },
+            &[seeds],
+        ))
+    }
+}
+
+// the optional side accounts a release books into
+pub struct ReleaseLedger<'a, 'info> {
+    pub payer_volume: &'a mut Option<Account<'info, VolumeAccount>>,
+    pub payer_reputation: &'a mut Option<Box<Account<'info, ReputationAccount>>>,
+    pub payee_reputation: &'a mut Option<Box<Account<'info, ReputationAccount>>>,
+    pub global_stats: &'a mut Option<Box<Account<'info, StatsAccount>>>,
+    pub mint_stats: &'a mut Option<Box<Account<'info, StatsAccount>>>,
+}
+
+// pays a release out and does its bookkeeping, whoever signed for it
+pub fn release_escrow<'info>(
+    vault: &Vault<'_, 'info>,
+    treasury: Party<'_, 'info>,
+    payee: Party<'_, 'info>,
+    remaining: &[AccountInfo<'info>],
+    ledger: ReleaseLedger<'_, 'info>,
+) -> Result<()> {
+    let escrow = vault.escrow;
+    let pot = escrow.deposited;
+    let (amount, fee) = vault.release(treasury, payee, remaining)?;
+    // only money that actually settled counts towards the payer's tiers
+    if let Some(volume) = ledger.payer_volume.as_mut().filter(|_| escrow.is_funded()) {
+        volume.volume = volume.volume.saturating_add(pot);
+    }
+    emit!(EscrowReleased {
+        address: escrow.key(),
+        amount,
+        tax_paid: fee,
+        token_mint: escrow.token_mint,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    if let Some(reputation) = ledger.payer_reputation.as_mut() {
+        reputation.completed_as_payer += 1;
+    }
+    if let Some(reputation) = ledger.payee_reputation.as_mut() {
+        reputation.completed_as_payee += 1;
+    }
+    record_stats(ledger.global_stats, ledger.mint_stats, StatsUpdate::Released { tax: fee });
+    Ok(())
+}
+
+// a token account handed in through remaining accounts, checked by hand since Anchor never saw it
+fn check_token_account(info: &AccountInfo, mint: Pubkey, owner: Pubkey) -> Result<()> {
+    if info.owner != &anchor_spl::token::ID {
+        return Err(error!(ErrorCode::WrongToken));
+    }
+    let token_account = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    if token_account.mint != mint {
+        return Err(error!(ErrorCode::WrongToken));
+    }
+    if token_account.owner != owner {
+        return Err(error!(ErrorCode::UninvolvedUser));
+    }
+    Ok(())
+}
+
+// pulls every extra asset leg in from the payer. remaining accounts hold, per leg, the payer's token account
+// and then the escrow's ATA for that mint (created ahead of time)
+pub fn deposit_legs<'info>(
+    escrow: &mut Account<'info, EscrowAccount>,
+    payer: &Signer<'info>,
+    token_program: Option<&Program<'info, Token>>,
+    remaining: &[AccountInfo<'info>],
+) -> Result<()> {
+    if escrow.legs.is_empty() {
+        return Ok(());
+    }
+    let Some(token_program) = token_program else {
+        return Err(error!(ErrorCode::MissingTokenAccounts));
+    };
+    if remaining.len() < escrow.legs.len() * 2 {
+        return Err(error!(ErrorCode::MissingTokenAccounts));
+    }
+    let escrow_key = escrow.key();
+    for (leg, accounts) in escrow.legs.iter_mut().zip(remaining.chunks(2)) {
+        let (payer_token_account, leg_vault) = (&accounts[0], &accounts[1]);
+        if leg_vault.key() != get_associated_token_address(&escrow_key, &leg.mint) {
+            return Err(error!(ErrorCode::InvalidLegVault));
+        }
+        check_token_account(payer_token_account, leg.mint, payer.key())?;
+        transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Transfer {
+                    from: payer_token_account.clone(),
+                    to: leg_vault.clone(),
+                    authority: payer.to_account_info(),
+                },
+            ),
+            leg.amount,
+        )?;
+        leg.deposited = leg.amount;
+        emit!(EscrowDeposited {
+            address: escrow_key,
+            amount: leg.amount,
+            token_mint: Some(leg.mint),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    Ok(())
+}
+
+//  ========================================================================================================  //
+//  Account Contexts                                                                                          //
+//    ▄████████  ▄████████  ▄████████     ███           ▄████████     ███     ▀████    ▐████▀    ▄████████    //
+//    ███    ███ ███    ███ ███    ███ ▀█████████▄      ███    ███ ▀█████████▄   ███▌   ████▀    ███    ███   //
+//    ███    ███ ███    █▀  ███    █▀     ▀███▀▀██      ███    █▀     ▀███▀▀██    ███  ▐███      ███    █▀    //
+//    ███    ███ ███        ███            ███   ▀      ███            ███   ▀    ▀███▄███▀      ███          //
+//  ▀███████████ ███        ███            ███          ███            ███        ████▀██▄     ▀███████████   //
+//    ███    ███ ███    █▄  ███    █▄      ███          ███    █▄      ███       ▐███  ▀███             ███   //
+//    ███    ███ ███    ███ ███    ███     ███          ███    ███     ███      ▄███     ███▄     ▄█    ███   //
+//    ███    █▀  ████████▀  ████████▀     ▄████▀        ████████▀     ▄████▀   ████       ███▄  ▄████████▀    //
+//  ========================================================================================================  //                                                               
+
+
+                                                                                                      
 
-                                                                                                      
-
+// Only the program's upgrade authority can launch, so forks and local validators work without recompiling
+#[cfg(not(feature = "authorized-launcher"))]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(
-        mut,
-        constraint = owner.key() == AUTHORIZED_LAUNCHER,
-    )]
+    #[account(mut)]
     pub owner: Signer<'info>,
     /// CHECK: treasury wallet address for deriving token accounts
     pub treasury: AccountInfo<'info>,
@@ -518,113 +2044,2231 @@ pub struct Initialize<'info> {
     )]
     pub config: Account<'info, ConfigAccount>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref()],
+        bump
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::InvalidProgramData,
+    )]
+    pub program: Program<'info, crate::program::Test>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(owner.key()) @ ErrorCode::NotUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Test builds can pin the launcher at compile time instead
+#[cfg(feature = "authorized-launcher")]
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        mut,
+        constraint = owner.key() == AUTHORIZED_LAUNCHER,
+    )]
+    pub owner: Signer<'info>,
+    /// CHECK: treasury wallet address for deriving token accounts
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ConfigAccount::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref()],
+        bump
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeNamespace<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: treasury wallet address for deriving token accounts
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ConfigAccount::INIT_SPACE,
+        seeds = [b"config", owner.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref()],
+        bump
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::UnauthorizedConfigOwner, // Ensures the signer matches config.admin
+    )]
+    pub config: Account<'info, ConfigAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetPausedContext<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = (authority.key() == config.admin || authority.key() == config.guardian) @ ErrorCode::UnauthorizedPauser,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+}
+
+// Old layouts can't deserialize as the current account types, so these take the raw accounts
+// and check owner + discriminator by hand in from_legacy
+#[derive(Accounts)]
+pub struct MigrateConfigContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any program-owned config, layout checked in ConfigAccount::from_legacy
+    #[account(mut, owner = crate::ID)]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref()],
+        bump
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEscrowContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: any program-owned escrow, layout checked in EscrowAccount::from_legacy
+    #[account(mut, owner = crate::ID)]
+    pub escrow: UncheckedAccount<'info>,
+
+    // token escrows only, so the deposit can be backfilled. has to be the escrow's ATA for its mint
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptJudgeSeatContext<'info> {
+    #[account(mut)]
+    pub pending_judge: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.pending_judge.is_some() @ ErrorCode::NoPendingJudge,
+        constraint = config.pending_judge.unwrap() == pending_judge.key() @ ErrorCode::UnauthorizedJudge,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdminSeatContext<'info> {
+    #[account(mut)]
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.pending_admin.is_some() @ ErrorCode::NoPendingAdmin,
+        constraint = config.pending_admin.unwrap() == pending_admin.key() @ ErrorCode::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetTaxTiersContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::UnauthorizedConfigOwner,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + TaxTiersAccount::INIT_SPACE,
+        seeds = [b"tax_tiers", config.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub tax_tiers: Account<'info, TaxTiersAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetFeeBoundsContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::UnauthorizedConfigOwner,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FeeBoundsAccount::INIT_SPACE,
+        seeds = [b"fee_bounds", config.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub fee_bounds: Account<'info, FeeBoundsAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedMintContext<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::UnauthorizedConfigOwner,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AllowedMintAccount::INIT_SPACE,
+        seeds = [b"allowed_mint", config.key().as_ref(), mint_account.key().as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMintAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedMintContext<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: This is admin pubkey, gets the rent back
+    #[account(
+        mut,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedConfigOwner,
+    )]
+    pub admin: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = (authority.key() == config.admin || authority.key() == config.guardian) @ ErrorCode::UnauthorizedMintRemoval,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"allowed_mint", config.key().as_ref(), allowed_mint.mint.as_ref()],
+        bump = allowed_mint.bump,
+        close = admin,
+    )]
+    pub allowed_mint: Account<'info, AllowedMintAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct OpenVolumeContext<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + VolumeAccount::INIT_SPACE,
+        seeds = [b"volume", config.key().as_ref(), wallet.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub volume: Account<'info, VolumeAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct OpenReputationContext<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        init,
+        payer = funder,
+        space = 8 + ReputationAccount::INIT_SPACE,
+        seeds = [b"reputation", wallet.as_ref()],
+        bump
+    )]
+    pub reputation: Account<'info, ReputationAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: EscrowCreationArgs)]
+pub struct CreateEscrowContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        seeds = [b"allowed_mint", config.key().as_ref(), args.token_mint.unwrap_or_default().as_ref()],
+        bump = allowed_mint.bump,
+    )]
+    pub allowed_mint: Option<Account<'info, AllowedMintAccount>>,
+
+    #[account(
+        seeds = [b"tax_tiers", config.key().as_ref(), args.token_mint.unwrap_or_default().as_ref()],
+        bump = tax_tiers.bump,
+    )]
+    pub tax_tiers: Option<Account<'info, TaxTiersAccount>>,
+
+    #[account(
+        seeds = [b"fee_bounds", config.key().as_ref(), args.token_mint.unwrap_or_default().as_ref()],
+        bump = fee_bounds.bump,
+    )]
+    pub fee_bounds: Option<Account<'info, FeeBoundsAccount>>, // snapshotted onto the escrow, judge fees stay plain BPS without it
+
+    #[account(
+        seeds = [b"volume", config.key().as_ref(), payer.key().as_ref(), args.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EscrowAccount::INIT_SPACE,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats", config.key().as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref(), args.token_mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub mint_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: EscrowCreationArgs)]
+pub struct CreateAndFundSolanaContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & (PAUSE_CREATE | PAUSE_DEPOSIT) == 0 @ ErrorCode::ProgramPaused,
+        constraint = args.token_mint.is_none() @ ErrorCode::EscrowNotSolana,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        seeds = [b"tax_tiers", config.key().as_ref(), Pubkey::default().as_ref()],
+        bump = tax_tiers.bump,
+    )]
+    pub tax_tiers: Option<Account<'info, TaxTiersAccount>>,
+
+    #[account(
+        seeds = [b"fee_bounds", config.key().as_ref(), Pubkey::default().as_ref()],
+        bump = fee_bounds.bump,
+    )]
+    pub fee_bounds: Option<Account<'info, FeeBoundsAccount>>, // snapshotted onto the escrow, judge fees stay plain BPS without it
+
+    #[account(
+        seeds = [b"volume", config.key().as_ref(), payer.key().as_ref(), Pubkey::default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EscrowAccount::INIT_SPACE,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", config.key().as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref(), Pubkey::default().as_ref()],
+        bump
+    )]
+    pub mint_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: EscrowCreationArgs)]
+pub struct CreateAndFundTokenContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & (PAUSE_CREATE | PAUSE_DEPOSIT) == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, ConfigAccount>>,
+
+    #[account(
+        constraint = args.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Box<Account<'info, Mint>>,
+
+    #[account(
+        seeds = [b"allowed_mint", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = allowed_mint.bump,
+    )]
+    pub allowed_mint: Option<Account<'info, AllowedMintAccount>>,
+
+    #[account(
+        seeds = [b"tax_tiers", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = tax_tiers.bump,
+    )]
+    pub tax_tiers: Option<Account<'info, TaxTiersAccount>>,
+
+    #[account(
+        seeds = [b"fee_bounds", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = fee_bounds.bump,
+    )]
+    pub fee_bounds: Option<Account<'info, FeeBoundsAccount>>, // snapshotted onto the escrow, judge fees stay plain BPS without it
+
+    #[account(
+        seeds = [b"volume", config.key().as_ref(), payer.key().as_ref(), mint_account.key().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EscrowAccount::INIT_SPACE,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, EscrowAccount>>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.mint == mint_account.key(),
+        constraint = payer_token_account.owner == payer.key(),
+    )]
+    pub payer_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = escrow,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", config.key().as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref(), mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Box<Account<'info, StatsAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: EscrowCreationArgs)]
+pub struct CreateNftEscrowContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        constraint = mint_account.decimals == 0 && mint_account.supply == 1 @ ErrorCode::NotAnNft,
+        constraint = mint_account.freeze_authority.is_none() @ ErrorCode::MintHasFreezeAuthority,
+    )]
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"allowed_mint", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = allowed_mint.bump,
+    )]
+    pub allowed_mint: Option<Account<'info, AllowedMintAccount>>,
+
+    #[account(
+        seeds = [b"tax_tiers", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = tax_tiers.bump,
+    )]
+    pub tax_tiers: Option<Account<'info, TaxTiersAccount>>,
+
+    #[account(
+        seeds = [b"volume", config.key().as_ref(), payer.key().as_ref(), mint_account.key().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EscrowAccount::INIT_SPACE,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", config.key().as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, StatsAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + StatsAccount::INIT_SPACE,
+        seeds = [b"stats", config.key().as_ref(), mint_account.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Box<Account<'info, StatsAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAssetLegContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.is_funded() @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"allowed_mint", config.key().as_ref(), mint_account.key().as_ref()],
+        bump = allowed_mint.bump,
+    )]
+    pub allowed_mint: Option<Account<'info, AllowedMintAccount>>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DepositCounterLegContext<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_DEPOSIT == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = escrow.is_swap() @ ErrorCode::NotASwap,
+        constraint = !escrow.counter_funded() @ ErrorCode::EscrowAlreadyFunded,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        constraint = escrow.counter.as_ref().map(|counter| counter.mint) == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = payee_token_account.mint == mint_account.key(),
+        constraint = payee_token_account.owner == payee.key(),
+    )]
+    pub payee_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payee,
+        associated_token::mint = mint_account,
+        associated_token::authority = escrow,
+    )]
+    pub counter_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct SettleSwapContext<'info> {
+    #[account(
+        mut,
+        constraint = (user.key() == escrow.payer || user.key() == escrow.payee) @ ErrorCode::UninvolvedUser,
+    )]
+    pub user: Signer<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.is_swap() @ ErrorCode::NotASwap,
+        constraint = escrow.is_funded() && escrow.counter_funded() @ ErrorCode::SwapNotFunded,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only (wSOL can skip the payee's)
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct CancelSwapContext<'info> {
+    #[account(
+        mut,
+        constraint = (user.key() == escrow.payer || user.key() == escrow.payee) @ ErrorCode::UninvolvedUser,
+    )]
+    pub user: Signer<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.is_swap() @ ErrorCode::NotASwap,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only, if the payer deposited (wSOL can skip the payer's)
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    // needed as soon as anything token-based was deposited
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct JudgeSwapContext<'info> {
+    #[account(mut)]
+    pub judge: Signer<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(mut)]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.is_swap() @ ErrorCode::NotASwap,
+        constraint = escrow.disputed @ ErrorCode::EscrowNotDisputed,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only (wSOL can skip the recipient's)
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub judge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct SetVestingContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.is_funded() @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVestedContext<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    /// CHECK: This is payer pubkey, gets the rent back after the last claim
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = escrow.vesting.is_some() @ ErrorCode::NotVesting,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", config.key().as_ref(), escrow.payer.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct SetRecurringContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.is_funded() @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReleasePeriodContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: This is payer pubkey, gets the rent back after the last period
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReceiving,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.recurring.is_some() @ ErrorCode::NotRecurring,
+        constraint = escrow.is_funded() @ ErrorCode::EscrowNotFunded,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", config.key().as_ref(), escrow.payer.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct JudgePeriodContext<'info> {
+    #[account(mut)]
+    pub judge: Signer<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(mut)]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.recurring.is_some() @ ErrorCode::NotRecurring,
+        constraint = escrow.disputed @ ErrorCode::EscrowNotDisputed,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub judge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct CancelRecurringContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReceiving,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.recurring.is_some() @ ErrorCode::NotRecurring,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only (wSOL can skip the payer's)
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", config.key().as_ref(), escrow.payer.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct SetPayeesContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_CREATE == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.is_funded() @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeShareContext<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.is_funded() @ ErrorCode::EscrowNotFunded,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", payee.key().as_ref()],
+        bump = disputer_reputation.bump,
+    )]
+    pub disputer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ReturnShareContext<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerReturning,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.is_funded() @ ErrorCode::EscrowNotFunded,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", payee.key().as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct JudgeShareContext<'info> {
+    #[account(mut)]
+    pub judge: Signer<'info>,
+
+    /// CHECK: the payee whose share is being judged, checked against escrow.payees
+    #[account(mut)]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(mut)]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub judge_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", payee.key().as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DisputeEscrowContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = (!escrow.disputed) @ ErrorCode::EscrowDisputed,
+        constraint = (user.key() == escrow.payer || user.key() == escrow.payee || user.key() == config.judge) @ ErrorCode::UninvolvedUser,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", user.key().as_ref()],
+        bump = disputer_reputation.bump,
+    )]
+    pub disputer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DepositSolanaContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_DEPOSIT == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
+        constraint = escrow.deposited == 0 @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // only needed when the escrow has asset legs
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DepositTokenContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_DEPOSIT == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
+        constraint = escrow.deposited == 0 @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.mint == mint_account.key(),
+        constraint = payer_token_account.owner == payer.key(),
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DepositWrappedSolContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_DEPOSIT == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.is_wrapped_sol() @ ErrorCode::EscrowNotWrappedSol,
+        constraint = escrow.deposited == 0 @ ErrorCode::EscrowAlreadyFunded,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(address = NATIVE_MINT)]
+    pub mint_account: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = escrow,
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct IncreaseAmountContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.paused & PAUSE_DEPOSIT == 0 @ ErrorCode::ProgramPaused,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        constraint = !escrow.nft @ ErrorCode::NftAmountFixed,
+        constraint = escrow.is_funded() @ ErrorCode::EscrowNotFunded,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.vesting.is_none() @ ErrorCode::VestingEscrow,
+        constraint = escrow.recurring.is_none() @ ErrorCode::RecurringEscrow,
+        constraint = escrow.payees.is_empty() @ ErrorCode::SplitEscrow,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(
+        mut,
+        constraint = Some(payer_token_account.mint) == escrow.token_mint @ ErrorCode::WrongToken,
+        constraint = payer_token_account.owner == payer.key(),
+    )]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = Some(escrow_token_account.mint) == escrow.token_mint @ ErrorCode::WrongToken,
+        constraint = escrow_token_account.owner == escrow.key(),
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct AmendAmountContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub payee: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        constraint = !escrow.nft @ ErrorCode::NftAmountFixed,
+        // lowering it would quietly shrink every period still to come
+        constraint = escrow.recurring.is_none() @ ErrorCode::RecurringEscrow,
+        constraint = !(escrow.is_wrapped_sol() && escrow.is_funded()) @ ErrorCode::WrappedSolPartialPayout,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only
+    #[account(
+        mut,
+        constraint = Some(payer_token_account.mint) == escrow.token_mint @ ErrorCode::WrongToken,
+        constraint = payer_token_account.owner == payer.key(),
+    )]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = Some(escrow_token_account.mint) == escrow.token_mint @ ErrorCode::WrongToken,
+        constraint = escrow_token_account.owner == escrow.key(),
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ReleaseContext<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReceiving,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only. recipients' ATAs get created if they don't exist yet (wSOL can skip the payee's)
+    #[account(
+        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Option<Box<Account<'info, Mint>>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = payee,
+    )]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", config.key().as_ref(), escrow.payer.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ApproveReleaseContext<'info> {
+    pub approver: Signer<'info>,
+
+    /// CHECK: This is payer pubkey, gets the rent back
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(
+        mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReceiving,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.approver == Some(approver.key()) @ ErrorCode::NotApprover,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        constraint = escrow.is_funded() @ ErrorCode::EscrowNotFunded,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only. the approver pays for nothing, so recipients' ATAs must already exist (wSOL can skip the payee's)
+    #[account(
+        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Option<Box<Account<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_account,
+        associated_token::authority = payee,
+    )]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_account,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"volume", config.key().as_ref(), escrow.payer.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = payer_volume.bump,
+    )]
+    pub payer_volume: Option<Account<'info, VolumeAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct SetApproverContext<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReturnContext<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(
+        mut,
+        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerReturning,
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", payer.key().as_ref()],
+        bump = escrow.bump,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.payees.is_empty() @ ErrorCode::SplitEscrow,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReturning,
+        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    // token escrows only. recipients' ATAs get created if they don't exist yet (wSOL can skip the payer's)
+    #[account(
+        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
+    )]
+    pub mint_account: Option<Box<Account<'info, Mint>>>,
+
+    #[account(
+        init_if_needed,
+        payer = payee,
+        associated_token::mint = mint_account,
+        associated_token::authority = payer,
+    )]
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = payee,
+        associated_token::mint = mint_account,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
+    )]
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
+    )]
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct JudgeContext<'info> {
+    #[account(mut)]
+    pub judge: Signer<'info>,
+
+    /// CHECK: This is payee pubkey
+    #[account(mut)]
+    pub payee: AccountInfo<'info>,
+
+    /// CHECK: This is payer pubkey
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: This is treasury pubkey
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
+        bump = config.bump,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.payer.as_ref()],
+        bump = escrow.bump,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.recurring.is_none() @ ErrorCode::RecurringEscrow,
+        constraint = !escrow.has_disputed_share() @ ErrorCode::ShareDisputed,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.disputed @ ErrorCode::EscrowNotDisputed,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
+        close = payer,
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
 
-#[derive(Accounts)]
-pub struct UpdateContext<'info> {
-    #[account(mut)]
-    pub judge: Signer<'info>,
-    
+    // token escrows only. recipients' ATAs get created if they don't exist yet (wSOL can skip the winner's)
     #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump,
-        has_one = judge, // Ensures the signer matches config.judge
+        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
     )]
-    pub config: Account<'info, ConfigAccount>,
-}
-
-#[derive(Accounts)]
-pub struct AcceptJudgeSeatContext<'info> {
-    #[account(mut)]
-    pub pending_judge: Signer<'info>,
+    pub mint_account: Option<Box<Account<'info, Mint>>>,
 
     #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump,
-        constraint = config.pending_judge.is_some() @ ErrorCode::NoPendingJudge,
-        constraint = config.pending_judge.unwrap() == pending_judge.key() @ ErrorCode::UnauthorizedJudge,
+        init_if_needed,
+        payer = judge,
+        associated_token::mint = mint_account,
+        associated_token::authority = payer,
     )]
-    pub config: Account<'info, ConfigAccount>,
-}
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
-#[derive(Accounts)]
-pub struct CreateEscrowContext<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = judge,
+        associated_token::mint = mint_account,
+        associated_token::authority = payee,
+    )]
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        init_if_needed,
+        payer = judge,
+        associated_token::mint = mint_account,
+        associated_token::authority = treasury,
     )]
-    pub config: Account<'info, ConfigAccount>,
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
     #[account(
-        init,
-        payer = payer,
-        space = 8 + EscrowAccount::INIT_SPACE,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump
+        init_if_needed,
+        payer = judge,
+        associated_token::mint = mint_account,
+        associated_token::authority = judge,
     )]
-    pub escrow: Account<'info, EscrowAccount>,
-    
-    pub system_program: Program<'info, System>,
-}
+    pub judge_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
-#[derive(Accounts)]
-pub struct DisputeEscrowContext<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        mut,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
     )]
-    pub config: Account<'info, ConfigAccount>,
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
 
     #[account(
         mut,
-        seeds = [b"escrow", escrow.payer.as_ref()],
-        bump = escrow.bump,
-        constraint = (!escrow.disputed) @ ErrorCode::EscrowDisputed,
-        constraint = (user.key() == escrow.payer || user.key() == escrow.payee || user.key() == config.judge) @ ErrorCode::UninvolvedUser,
+        seeds = [b"reputation", escrow.payee.as_ref()],
+        bump = payee_reputation.bump,
     )]
-    pub escrow: Account<'info, EscrowAccount>,
-
-    pub system_program: Program<'info, System>
-}
-
-#[derive(Accounts)]
-pub struct DepositSolanaContext<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    pub payee_reputation: Option<Box<Account<'info, ReputationAccount>>>,
 
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
     )]
-    pub config: Account<'info, ConfigAccount>,
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
 
     #[account(
         mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
 
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>
 }
 
 #[derive(Accounts)]
-pub struct DepositTokenContext<'info> {
+pub struct RecoverContext<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    /// CHECK: This is treasury pubkey, taxes whatever had vested
     #[account(
-        seeds = [b"config"],
+        mut,
+        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", config.namespace_seed()],
         bump = config.bump,
     )]
     pub config: Account<'info, ConfigAccount>,
@@ -633,544 +4277,1147 @@ pub struct DepositTokenContext<'info> {
         mut,
         seeds = [b"escrow", payer.key().as_ref()],
         bump = escrow.bump,
-        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
+        constraint = !escrow.is_swap() @ ErrorCode::SwapEscrow,
+        constraint = escrow.config == config.key() @ ErrorCode::WrongConfig,
+        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerRecovering,
+        close = payer,
     )]
     pub escrow: Account<'info, EscrowAccount>,
 
+    /// CHECK: This is payee pubkey, gets whatever had vested
     #[account(
         mut,
+        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
+    )]
+    pub payee: AccountInfo<'info>,
+
+    // token escrows only. recipients' ATAs get created if they don't exist yet (wSOL can skip the payer's,
+    // and the payee's and treasury's are only needed for vesting escrows)
+    #[account(
         constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
     )]
-    pub mint_account: Account<'info, Mint>,
+    pub mint_account: Option<Box<Account<'info, Mint>>>,
 
     #[account(
-        mut,
-        constraint = payer_token_account.mint == mint_account.key(),
-        constraint = payer_token_account.owner == payer.key(),
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = payer,
     )]
-    pub payer_token_account: Account<'info, TokenAccount>,
+    pub payer_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = mint_account,
-        associated_token::authority = escrow,
+        associated_token::authority = payee,
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub payee_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
-}
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_account,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
-#[derive(Accounts)]
-pub struct RecoverTokenContext<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub escrow_token_account: Option<Box<Account<'info, TokenAccount>>>,
 
     #[account(
         mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
+        seeds = [b"reputation", escrow.payer.as_ref()],
+        bump = payer_reputation.bump,
     )]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub payer_reputation: Option<Box<Account<'info, ReputationAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref()],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", escrow.config.as_ref(), escrow.token_mint.unwrap_or_default().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Option<Box<Account<'info, StatsAccount>>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>
+}
+// ================================================================================================================================  //
+// Arg Structs - Function Argument Definitions                                                                                       //
+//   ▄████████    ▄████████    ▄██████▄          ▄████████     ███        ▄████████ ███    █▄   ▄████████     ███        ▄████████   // 
+//   ███    ███   ███    ███   ███    ███        ███    ███ ▀█████████▄   ███    ███ ███    ███ ███    ███ ▀█████████▄   ███    ███  // 
+//   ███    ███   ███    ███   ███    █▀         ███    █▀     ▀███▀▀██   ███    ███ ███    ███ ███    █▀     ▀███▀▀██   ███    █▀   // 
+//   ███    ███  ▄███▄▄▄▄██▀  ▄███               ███            ███   ▀  ▄███▄▄▄▄██▀ ███    ███ ███            ███   ▀   ███         // 
+// ▀███████████ ▀▀███▀▀▀▀▀   ▀▀███ ████▄       ▀███████████     ███     ▀▀███▀▀▀▀▀   ███    ███ ███            ███     ▀███████████  // 
+//   ███    ███ ▀███████████   ███    ███               ███     ███     ▀███████████ ███    ███ ███    █▄      ███              ███  // 
+//   ███    ███   ███    ███   ███    ███         ▄█    ███     ███       ███    ███ ███    ███ ███    ███     ███        ▄█    ███  // 
+//   ███    █▀    ███    ███   ████████▀        ▄████████▀     ▄████▀     ███    ███ ████████▀  ████████▀     ▄████▀    ▄████████▀   // 
+//                ███    ███                                              ███    ███                                                 // 
+// ================================================================================================================================  //
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ConfigUpdateArgs {
+    pub treasury: Option<Pubkey>,
+    pub pending_judge: Option<Pubkey>,
+    pub pending_admin: Option<Pubkey>,
+    pub tax: Option<u16>,
+    pub fee: Option<u16>,
+    pub judge_share: Option<u16>,
+    pub timelock: Option<i64>,
+    pub guardian: Option<Pubkey>,
+    pub mint_policy: Option<MintPolicyUpdate>, // queued alone, applied by the matching set_*/add_* instruction
+}
+
+impl ConfigUpdateArgs {
+    pub fn has_config_changes(&self) -> bool {
+        self.treasury.is_some()
+            || self.pending_judge.is_some()
+            || self.pending_admin.is_some()
+            || self.tax.is_some()
+            || self.fee.is_some()
+            || self.judge_share.is_some()
+            || self.timelock.is_some()
+            || self.guardian.is_some()
+    }
+}
+
+// per-mint policy changes, timelocked like everything else the admin can touch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum MintPolicyUpdate {
+    TaxTiers {
+        mint: Pubkey,
+        #[max_len(MAX_TAX_TIERS)]
+        tiers: Vec<TaxTier>,
+    },
+    FeeBounds { mint: Pubkey, min_fee: u64, max_fee: u64 },
+    AllowMint { mint: Pubkey },
+}
+
+impl MintPolicyUpdate {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            MintPolicyUpdate::TaxTiers { tiers, .. } => {
+                if tiers.len() > MAX_TAX_TIERS {
+                    return Err(error!(ErrorCode::TooManyTaxTiers));
+                }
+                // thresholds go up, taxes never go up
+                for pair in tiers.windows(2) {
+                    if pair[1].threshold <= pair[0].threshold || pair[1].tax > pair[0].tax {
+                        return Err(error!(ErrorCode::InvalidTaxTiers));
+                    }
+                }
+                if tiers.iter().any(|tier| tier.tax > 2000) {
+                    return Err(error!(ErrorCode::TaxTooHigh));
+                }
+            }
+            MintPolicyUpdate::FeeBounds { min_fee, max_fee, .. } => {
+                if min_fee > max_fee {
+                    return Err(error!(ErrorCode::InvalidFeeBounds));
+                }
+            }
+            MintPolicyUpdate::AllowMint { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct AssetLeg {
+    pub mint: Pubkey,
+    pub amount: u64,    // what the payer owes for this leg
+    pub deposited: u64, // what's actually sitting in the escrow's ATA for this mint
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Vesting {
+    pub steps: u16,     // 0 unlocks linearly, otherwise in this many equal cliffs
+    pub claimed: u64,   // what the payee has already taken out (no longer counted in deposited)
+    pub frozen_at: i64, // set when the escrow gets disputed, nothing unlocks after this. 0 while running
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Recurring {
+    pub periods: u16,       // how many payouts the deposit covers
+    pub interval: i64,      // seconds between payouts, period n unlocks at creation_time + n * interval
+    pub released: u16,      // payouts made so far (released or judged)
+    pub auto_release: bool, // anyone can release an unlocked period, otherwise only the payer can
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PayeeShare {
+    pub payee: Pubkey,
+    pub bps: u16,       // share of the payee side, all shares add up to 10000
+    pub disputed: bool, // this payee raised a dispute over their own share
+    pub settled: bool,  // share already returned or judged on its own, it's out of the split
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PayeeShareArgs {
+    pub payee: Pubkey,
+    pub bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct TaxTier {
+    pub threshold: u64, // cumulative volume (smallest units) needed to unlock this tier
+    pub tax: u16,       // BPS tax once unlocked
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct EscrowCreationArgs {
+    pub amount: u64,
+    pub payee: Pubkey,
+    pub deadline: i64,
+    pub judge_deadline: i64,
+    pub token_mint: Option<Pubkey>,
+    pub approver: Option<Pubkey>
+}
+
+// ========================================================================================================== //
+// Account Definitions                                                                                        //
+//   ▄████████  ▄████████  ▄████████     ███          ████████▄     ▄████████    ▄████████    ▄████████       //
+//   ███    ███ ███    ███ ███    ███ ▀█████████▄      ███   ▀███   ███    ███   ███    ███   ███    ███      //
+//   ███    ███ ███    █▀  ███    █▀     ▀███▀▀██      ███    ███   ███    █▀    ███    █▀    ███    █▀       //
+//   ███    ███ ███        ███            ███   ▀      ███    ███  ▄███▄▄▄      ▄███▄▄▄       ███             //
+// ▀███████████ ███        ███            ███          ███    ███ ▀▀███▀▀▀     ▀▀███▀▀▀     ▀███████████      //
+//   ███    ███ ███    █▄  ███    █▄      ███          ███    ███   ███    █▄    ███                 ███      //
+//   ███    ███ ███    ███ ███    ███     ███          ███   ▄███   ███    ███   ███           ▄█    ███      //
+//   ███    █▀  ████████▀  ████████▀     ▄████▀        ████████▀    ██████████   ███         ▄████████▀       //
+// ========================================================================================================== //     
+
+
+                                                                                                    
+
+#[account]
+#[derive(InitSpace)]
+pub struct ConfigAccount {
+    pub version: u8, // CONFIG_VERSION, always first so migrate_config can find it
+    pub namespace: Pubkey, // DEFAULT_NAMESPACE for the protocol config, otherwise the partner that created it
+    pub admin: Pubkey, // Controls treasury, taxes, fees and the allowlist
+    pub judge: Pubkey, // Rules on disputes, nothing else
+    pub guardian: Pubkey, // Can pause new escrows and deposits in an emergency
+    pub treasury: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub pending_judge: Option<Pubkey>,
+    pub tax: u16, // BPS Fee for all future transactions
+    pub fee: u16, // BPS fee to incentivize the DAO to rule on escrows
+    pub judge_share: u16, // BPS of the judge fee paid to the judging signer, the rest goes to treasury
+    pub timelock: i64, // seconds a queued config update waits before it can execute
+    pub queued_update: Option<ConfigUpdateArgs>, // the change waiting out the timelock, if any
+    pub queued_eta: i64, // when queued_update becomes executable
+    pub paused: u8, // PAUSE_* bits, exits are never paused
+    pub bump: u8, // Store the bump for verification later
+}
+
+impl ConfigAccount {
+    pub fn init(&mut self, namespace: Pubkey, owner: Pubkey, treasury: Pubkey, tax: u16, fee: u16, bump: u8) -> Result<()> {
+        if tax > 2000 {
+            return Err(error!(ErrorCode::TaxTooHigh));
+        }
+        if fee > 2000 {
+            return Err(error!(ErrorCode::FeeTooHigh));
+        }
+        self.version = CONFIG_VERSION;
+        self.namespace = namespace;
+        self.admin = owner;
+        self.judge = owner;
+        self.guardian = owner;
+        self.treasury = treasury;
+        self.tax = tax;
+        self.fee = fee;
+        self.timelock = DEFAULT_CONFIG_TIMELOCK;
+        self.bump = bump;
+        Ok(())
+    }
+
+    // clears the queued mint policy change if it's exactly `update` and its timelock is up
+    pub fn take_mint_policy(&mut self, update: MintPolicyUpdate, now: i64) -> Result<()> {
+        let Some(queued) = self.queued_update.as_ref().and_then(|updates| updates.mint_policy.as_ref()) else {
+            return Err(error!(ErrorCode::NoQueuedConfigUpdate));
+        };
+        if *queued != update {
+            return Err(error!(ErrorCode::MintPolicyMismatch));
+        }
+        if now < self.queued_eta {
+            return Err(error!(ErrorCode::ConfigUpdateTimelocked));
+        }
+        self.queued_update = None;
+        self.queued_eta = 0;
+        Ok(())
+    }
+
+    // PDA seed for the namespace, the protocol config keeps its original [b"config"] address
+    pub fn namespace_seed(&self) -> &[u8] {
+        if self.namespace == DEFAULT_NAMESPACE {
+            &[]
+        } else {
+            self.namespace.as_ref()
+        }
+    }
+
+    // guardians can pull the brake, only the admin can let go of it
+    pub fn check_pause(&self, authority: Pubkey, paused: u8) -> Result<()> {
+        if paused & !PAUSE_ALL != 0 {
+            return Err(error!(ErrorCode::InvalidPauseFlags));
+        }
+        if authority != self.admin && paused & self.paused != self.paused {
+            return Err(error!(ErrorCode::GuardianCannotUnpause));
+        }
+        Ok(())
+    }
+
+    // (judge's cut, treasury's cut) of a judge fee
+    pub fn split_judge_fee(&self, fee: u64) -> (u64, u64) {
+        let judge_paid = ((fee as u128 * self.judge_share as u128) / 10000) as u64;
+        (judge_paid, fee - judge_paid)
+    }
+
+    // repeat customers get whatever tier their lifetime volume (plus this escrow) unlocks
+    pub fn escrow_tax(&self, tax_tiers: Option<&TaxTiersAccount>, payer_volume: Option<&VolumeAccount>, amount: u64) -> u16 {
+        match tax_tiers {
+            Some(tax_tiers) => {
+                let history = payer_volume.map_or(0, |v| v.volume);
+                tax_tiers.tax_for(history.saturating_add(amount), self.tax)
+            }
+            None => self.tax,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EscrowAccount {
+    pub version: u8,                // ESCROW_VERSION, always first so migrate_escrow can find it
+    pub config: Pubkey,             // The config (and so judge, treasury, fee policy) this escrow answers to
+    pub payer: Pubkey,              // The person depositing funds
+    pub payee: Pubkey,              // The recipient who should receive funds
+    pub amount: u64,                // Amount held in escrow
+    pub deposited: u64,             // What the payer has actually put in, 0 until funded
+    pub tax: u16,                   // the tax at time of escrow creation, ie the tax amount Payer and Payee agreed to when escrow was created. BPS.
+    pub fee: u16,                   // the fee at time of escrow creation, ie the fee amount Payer and Payee agreed to when escrow was created. BPS.
+    pub min_fee: u64,               // the mint's judge fee bounds at creation, 0 and u64::MAX if it had none
+    pub max_fee: u64,
+    pub token_mint: Option<Pubkey>, // If None, this is a SOL escrow, otherwise an SPL token
+    #[max_len(MAX_ASSET_LEGS)]
+    pub legs: Vec<AssetLeg>,        // extra tokens that move together with the main asset
+    pub nft: bool,                  // main asset is a single indivisible token, so no in-kind tax or judge fee
+    pub counter: Option<AssetLeg>,  // swaps only - what the payee puts in, paid to the payer when the trade goes through
+    pub vesting: Option<Vesting>,   // if set, the payee claims the deposit bit by bit between creation_time and deadline
+    pub recurring: Option<Recurring>, // if set, the deposit is paid out one period per interval
+    #[max_len(MAX_PAYEES)]
+    pub payees: Vec<PayeeShare>,    // split escrows only - everyone the payee side is shared between, payee included
+    pub approver: Option<Pubkey>,   // can release in the payer's place, nothing else
+    pub disputed: bool,             
+    pub deadline: i64,              // judge has to wait til after this time to raise a dispute
+    pub judge_deadline: i64,
+    pub creation_time: i64,         // When escrow was created (unix timestamp)
+    pub bump: u8,                   // Bump for PDA verification
+}
+
+impl EscrowAccount {
+    // everything create_escrow sets up, shared with the create_and_fund_* instructions
+    pub fn open(&mut self, config: &Account<ConfigAccount>, payer: Pubkey, args: &EscrowCreationArgs, tax: u16, fee_bounds: Option<&FeeBoundsAccount>, bump: u8) -> Result<()> {
+        self.version = ESCROW_VERSION;
+        self.config = config.key();
+        self.payer = payer;
+        self.payee = args.payee;
+        let now = Clock::get()?.unix_timestamp;
+        self.creation_time = now;
+        self.deadline = now + (14 * 24 * 60 * 60);
+        self.judge_deadline = self.deadline + JUDGE_WINDOW;
+        self.amount = args.amount;
+        self.token_mint = args.token_mint;
+        self.tax = tax;
+        self.fee = config.fee;
+        // the bounds are part of the deal too, later changes only apply to new escrows
+        self.min_fee = fee_bounds.map_or(0, |bounds| bounds.min_fee);
+        self.max_fee = fee_bounds.map_or(u64::MAX, |bounds| bounds.max_fee);
+        self.bump = bump;
+        self.set_approver(args.approver)
+    }
+
+    // an approver only ever releases to the payee side, so the payee can't be one
+    pub fn set_approver(&mut self, approver: Option<Pubkey>) -> Result<()> {
+        if approver.is_some_and(|approver| approver == self.payee || self.payees.iter().any(|share| share.payee == approver)) {
+            return Err(error!(ErrorCode::InvalidApprover));
+        }
+        self.approver = approver;
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
-    )]
-    pub mint_account: Account<'info, Mint>,
+    // the tax on an amount has to come to at least one unit or there's nothing to collect
+    pub fn check_amount(amount: u64, tax: u16) -> Result<()> {
+        if ((amount as u128 * tax as u128) / 10000) < 1 {
+            return Err(error!(ErrorCode::InvalidEscrowAmount));
+        }
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = payer_token_account.mint == mint_account.key(),
-        constraint = payer_token_account.owner == payer.key(),
-    )]
-    pub payer_token_account: Account<'info, TokenAccount>,
+    // basis point tax owed on a payout
+    pub fn tax_due(&self, amount: u64) -> u64 {
+        ((amount as u128 * self.tax as u128) / 10000) as u64
+    }
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = mint_account,
-        associated_token::authority = escrow,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    // the judge fee on the main asset, clamped by the bounds agreed at creation. NFTs can't be split so they never pay one
+    pub fn judge_fee(&self, amount: u64) -> u64 {
+        if self.nft {
+            return 0;
+        }
+        self.fee_due(amount).clamp(self.min_fee, self.max_fee).min(amount)
+    }
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
-}
+    // basis point judge fee, for legs that have no fee bounds of their own
+    pub fn fee_due(&self, amount: u64) -> u64 {
+        ((amount as u128 * self.fee as u128) / 10000) as u64
+    }
 
-#[derive(Accounts)]
-pub struct ReleaseTokenContext<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    // unwrapping closes the vault, so wSOL escrows only ever pay out in one go
+    pub fn is_wrapped_sol(&self) -> bool {
+        self.token_mint == Some(NATIVE_MINT)
+    }
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payee: AccountInfo<'info>,
+    pub fn is_funded(&self) -> bool {
+        self.deposited > 0
+    }
 
-    /// CHECK: This is treasury pubkey
-    #[account(mut,
-        constraint = config.treasury == treasury.key()
-    )]
-    pub treasury: AccountInfo<'info>,
+    pub fn is_swap(&self) -> bool {
+        self.counter.is_some()
+    }
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump
-    )]
-    pub config: Account<'info, ConfigAccount>,
+    pub fn counter_funded(&self) -> bool {
+        self.counter.as_ref().is_some_and(|counter| counter.deposited > 0)
+    }
 
-    #[account(
-        mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+    // neither side of a swap can fund after the deadline, or the late side would get a free option on the trade
+    pub fn swap_expired(&self, now: i64) -> bool {
+        self.is_swap() && now > self.deadline
+    }
 
-    #[account(
-        mut,
-        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
-    )]
-    pub mint_account: Account<'info, Mint>,
+    // cancel_swap waits until after this. a funded or disputed swap waits out the judge, same as recover
+    // the judge can step in once the escrow should have settled on its own. recurring escrows run that clock per
+    // period, so it's whenever the next period falls due rather than the end of the schedule
+    pub fn dispute_opens(&self) -> i64 {
+        if self.recurring.is_some() { self.next_period_unlocks() } else { self.deadline }
+    }
 
-    #[account(
-        mut,
-        constraint = payee_token_account.mint == mint_account.key(),
-        constraint = payee_token_account.owner == payee.key(),
-    )]
-    pub payee_token_account: Account<'info, TokenAccount>,
+    // the judge rules up to judge_deadline, after that it's recover's turn. a recurring period gets the usual window
+    // from when it fell due
+    pub fn judge_window_closed(&self, now: i64) -> bool {
+        let closes = if self.recurring.is_some() {
+            self.next_period_unlocks().saturating_add(JUDGE_WINDOW).min(self.judge_deadline)
+        } else {
+            self.judge_deadline
+        };
+        now > closes
+    }
 
-    #[account(
-        mut,
-        constraint = treasury_token_account.mint == mint_account.key(),
-        constraint = treasury_token_account.owner == treasury.key(),
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
+    pub fn swap_open_until(&self) -> i64 {
+        if (self.is_funded() && self.counter_funded()) || self.disputed { self.judge_deadline } else { self.deadline }
+    }
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = mint_account,
-        associated_token::authority = escrow,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    // split escrows: an amount shared by bps among the payees still in the split, the last one takes the rounding
+    pub fn split_among_payees(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        let open: Vec<&PayeeShare> = self.payees.iter().filter(|share| !share.settled).collect();
+        let open_bps: u128 = open.iter().map(|share| share.bps as u128).sum();
+        let mut left = amount;
+        open.iter()
+            .enumerate()
+            .map(|(i, share)| {
+                let cut = if i + 1 == open.len() { left } else { (amount as u128 * share.bps as u128 / open_bps) as u64 };
+                left -= cut;
+                (share.payee, cut)
+            })
+            .collect()
+    }
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
-}
+    // what one payee's open share of the deposit is worth
+    pub fn share_of(&self, payee: Pubkey) -> u64 {
+        self.split_among_payees(self.deposited)
+            .into_iter()
+            .find_map(|(key, cut)| (key == payee).then_some(cut))
+            .unwrap_or(0)
+    }
 
-#[derive(Accounts)]
-pub struct ReturnTokenContext<'info> {
-    #[account(mut)]
-    pub payee: Signer<'info>,
+    // takes a settled share out of the split, true once nobody is left in it
+    pub fn settle_share(&mut self, payee: Pubkey, amount: u64) -> bool {
+        self.deposited -= amount;
+        if let Some(share) = self.payees.iter_mut().find(|share| share.payee == payee) {
+            share.settled = true;
+            share.disputed = false;
+        }
+        self.payees.iter().all(|share| share.settled)
+    }
 
-    /// CHECK: This is payer pubkey
-    #[account(
-        mut,
-        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
-    )]
-    pub payer: AccountInfo<'info>,
+    pub fn has_disputed_share(&self) -> bool {
+        self.payees.iter().any(|share| share.disputed && !share.settled)
+    }
 
-    /// CHECK: This is treasury pubkey
-    #[account(
-        mut,
-        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
-    )]
-    pub treasury: AccountInfo<'info>,
+    // the next payout on a recurring escrow - an even share of what's left, the last period takes the rounding
+    pub fn next_period_amount(&self) -> u64 {
+        let Some(recurring) = &self.recurring else {
+            return 0;
+        };
+        match recurring.periods.saturating_sub(recurring.released) {
+            0 => 0,
+            1 => self.deposited,
+            left => self.deposited / left as u64,
+        }
+    }
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
-    )]
-    pub config: Account<'info, ConfigAccount>,
+    // when the next period can be released without the payer
+    pub fn next_period_unlocks(&self) -> i64 {
+        self.recurring.as_ref().map_or(i64::MAX, |recurring| {
+            self.creation_time + (recurring.released as i64 + 1) * recurring.interval
+        })
+    }
 
-    #[account(
-        mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
-        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
-        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+    // takes one period's payout off the books, true when that was the last one
+    pub fn record_period(&mut self, amount: u64) -> bool {
+        self.deposited -= amount;
+        self.recurring.as_mut().is_some_and(|recurring| {
+            recurring.released += 1;
+            recurring.released >= recurring.periods
+        })
+    }
 
-    #[account(
-        mut,
-        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
-    )]
-    pub mint_account: Account<'info, Mint>,
+    // what cancel_recurring still owes the payee: every period that already unlocked, but only with auto release
+    pub fn unlocked_periods_amount(&self, now: i64) -> u64 {
+        if !self.recurring.as_ref().is_some_and(|recurring| recurring.auto_release) {
+            return 0;
+        }
+        let mut schedule = self.clone();
+        let mut owed = 0;
+        while schedule.deposited > 0 && now >= schedule.next_period_unlocks() {
+            let pot = schedule.next_period_amount();
+            owed += pot;
+            if schedule.record_period(pot) {
+                break;
+            }
+        }
+        owed
+    }
 
-    #[account(
-        mut,
-        constraint = payer_token_account.mint == mint_account.key(),
-        constraint = payer_token_account.owner == payee.key(),
-    )]
-    pub payer_token_account: Account<'info, TokenAccount>,
+    // what the payee could claim right now on a vesting escrow. the schedule runs from creation_time to deadline
+    // over everything ever deposited, and stops at frozen_at once disputed
+    pub fn vested_unclaimed(&self, now: i64) -> u64 {
+        let Some(vesting) = &self.vesting else {
+            return 0;
+        };
+        let until = if vesting.frozen_at > 0 { now.min(vesting.frozen_at) } else { now };
+        let duration = (self.deadline - self.creation_time).max(1) as u128;
+        let elapsed = (until - self.creation_time).clamp(0, duration as i64) as u128;
+        let total = (self.deposited + vesting.claimed) as u128;
+        let unlocked = match vesting.steps {
+            0 => total * elapsed / duration,
+            steps => total * (elapsed * steps as u128 / duration) / steps as u128,
+        } as u64;
+        unlocked.saturating_sub(vesting.claimed).min(self.deposited)
+    }
 
-    #[account(
-        mut,
-        constraint = treasury_token_account.mint == mint_account.key(),
-        constraint = treasury_token_account.owner == treasury.key(),
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
+    // a top-up goes in on the spot, so amount and deposited move together. returns the old amount
+    pub fn top_up(&mut self, additional: u64) -> Result<u64> {
+        let new_amount = self.amount.checked_add(additional).ok_or(error!(ErrorCode::InvalidEscrowAmount))?;
+        Self::check_amount(new_amount, self.tax)?;
+        let old_amount = self.amount;
+        self.amount = new_amount;
+        self.deposited += additional;
+        Ok(old_amount)
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = mint_account,
-        associated_token::authority = escrow,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    // what amend_amount hands back on a funded escrow. claimed vesting already left, so it counts towards the new amount
+    pub fn amend_refund(&self, new_amount: u64) -> u64 {
+        let claimed = self.vesting.as_ref().map_or(0, |vesting| vesting.claimed);
+        (self.deposited + claimed).saturating_sub(new_amount).min(self.deposited)
+    }
+}
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
+#[account]
+#[derive(InitSpace)]
+pub struct TaxTiersAccount {
+    pub mint: Pubkey,               // Pubkey::default() for SOL escrows
+    #[max_len(MAX_TAX_TIERS)]
+    pub tiers: Vec<TaxTier>,        // ascending thresholds, non-increasing taxes
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct RecoverSolanaContext<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+impl TaxTiersAccount {
+    // lowest tax unlocked by `volume`, never more than the flat config tax
+    pub fn tax_for(&self, volume: u64, flat_tax: u16) -> u16 {
+        self.tiers
+            .iter()
+            .filter(|tier| volume >= tier.threshold)
+            .fold(flat_tax, |tax, tier| tax.min(tier.tax))
+    }
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
-        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerRecovering,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+#[account]
+#[derive(InitSpace)]
+pub struct FeeBoundsAccount {
+    pub mint: Pubkey,               // Pubkey::default() for SOL escrows
+    pub min_fee: u64,               // judges always get at least this much (capped at the escrow amount)
+    pub max_fee: u64,               // and never more than this
+    pub bump: u8,
+}
 
-    pub system_program: Program<'info, System>
+#[account]
+#[derive(InitSpace)]
+pub struct AllowedMintAccount {
+    pub mint: Pubkey,               // a mint token escrows are allowed to use
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct ReleaseSolanaContext<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+#[account]
+#[derive(InitSpace)]
+pub struct VolumeAccount {
+    pub wallet: Pubkey,             // the payer whose settled escrows are being tallied
+    pub mint: Pubkey,               // Pubkey::default() for SOL escrows
+    pub volume: u64,                // lifetime settled amount, smallest units
+    pub bump: u8,
+}
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payee: AccountInfo<'info>,
+#[account]
+#[derive(InitSpace)]
+pub struct StatsAccount {
+    pub config: Pubkey,
+    pub mint: Option<Pubkey>,       // None for the config-wide rollup, Pubkey::default() for SOL
+    pub escrows_created: u64,
+    pub volume: u64,                // smallest units, per-mint only since mints don't add up
+    pub taxes_collected: u64,       // release/return taxes, per-mint only
+    pub judge_fees_collected: u64,  // judge + treasury shares of judge fees, per-mint only
+    pub disputes_opened: u64,
+    pub released: u64,
+    pub returned: u64,
+    pub rulings_for_payer: u64,
+    pub rulings_for_payee: u64,
+    pub recoveries: u64,
+    pub bump: u8,
+}
 
-    /// CHECK: This is treasury pubkey
-    #[account(mut,
-        constraint = config.treasury == treasury.key()
-    )]
-    pub treasury: AccountInfo<'info>,
+#[account]
+#[derive(InitSpace)]
+pub struct ReputationAccount {
+    pub wallet: Pubkey,             // whose track record this is, across every config
+    pub completed_as_payer: u64,    // released or returned while they were paying
+    pub completed_as_payee: u64,    // released or returned while they were getting paid
+    pub disputes_raised: u64,
+    pub disputes_won: u64,          // judge sent the funds their way
+    pub disputes_lost: u64,
+    pub recoveries: u64,            // pulled funds back after everyone went silent
+    pub bump: u8,
+}
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-    )]
-    pub config: Account<'info, ConfigAccount>,
+impl ReputationAccount {
+    pub fn record_ruling(&mut self, won: bool) {
+        if won {
+            self.disputes_won += 1;
+        } else {
+            self.disputes_lost += 1;
+        }
+    }
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
-        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerReleasing,
-        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReceiving,
-        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+#[derive(Clone, Copy)]
+pub enum StatsUpdate {
+    Created { amount: u64 },
+    Disputed,
+    Released { tax: u64 },
+    Returned { tax: u64 },
+    Judged { for_payee: bool, fee: u64 },
+    Recovered,
+}
 
-    pub system_program: Program<'info, System>
+impl StatsAccount {
+    // init_if_needed hands us zeroes the first time around
+    pub fn open(&mut self, config: Pubkey, mint: Option<Pubkey>, bump: u8) -> &mut Self {
+        self.config = config;
+        self.mint = mint;
+        self.bump = bump;
+        self
+    }
+
+    pub fn record(&mut self, update: StatsUpdate) {
+        let per_mint = self.mint.is_some();
+        match update {
+            StatsUpdate::Created { amount } => {
+                self.escrows_created += 1;
+                if per_mint {
+                    self.volume = self.volume.saturating_add(amount);
+                }
+            }
+            StatsUpdate::Disputed => self.disputes_opened += 1,
+            StatsUpdate::Released { tax } => {
+                self.released += 1;
+                if per_mint {
+                    self.taxes_collected = self.taxes_collected.saturating_add(tax);
+                }
+            }
+            StatsUpdate::Returned { tax } => {
+                self.returned += 1;
+                if per_mint {
+                    self.taxes_collected = self.taxes_collected.saturating_add(tax);
+                }
+            }
+            StatsUpdate::Judged { for_payee, fee } => {
+                if for_payee {
+                    self.rulings_for_payee += 1;
+                } else {
+                    self.rulings_for_payer += 1;
+                }
+                if per_mint {
+                    self.judge_fees_collected = self.judge_fees_collected.saturating_add(fee);
+                }
+            }
+            StatsUpdate::Recovered => self.recoveries += 1,
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct ReturnSolanaContext<'info> {
-    #[account(mut)]
-    pub payee: Signer<'info>,
+// stats only get created with configs and escrows, so settlements and disputes update whichever are passed
+pub fn record_stats<'info>(
+    global_stats: &mut Option<Box<Account<'info, StatsAccount>>>,
+    mint_stats: &mut Option<Box<Account<'info, StatsAccount>>>,
+    update: StatsUpdate,
+) {
+    for stats in [global_stats, mint_stats].into_iter().flatten() {
+        stats.record(update);
+    }
+}
 
-    /// CHECK: This is payer pubkey
-    #[account(mut)]
-    pub payer: AccountInfo<'info>,
+// ========================================================================= //
+// Legacy Layouts                                                            //
+//                                                                           //
+// Every released layout of a versioned account lives here, frozen, so       //
+// migrate_* can decode it. Bump *_VERSION, snapshot the outgoing layout as  //
+// *V<n>, point the newest From impl at it and add a match arm to            //
+// from_legacy whenever a field changes. Only layouts that actually shipped  //
+// get a snapshot.                                                           //
+// ========================================================================= //
 
-    /// CHECK: This is treasury pubkey
-    #[account(
-        mut, 
-        constraint = config.treasury == treasury.key()
-    )]
-    pub treasury: AccountInfo<'info>,
+// Before versioning - the launch layout, identified by account size since it has no version byte
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct ConfigAccountV0 {
+    pub judge: Pubkey,
+    pub treasury: Pubkey,
+    pub pending_judge: Option<Pubkey>,
+    pub tax: u16,
+    pub fee: u8, // percentage, not BPS
+    pub bump: u8,
+}
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-    )]
-    pub config: Account<'info, ConfigAccount>,
+impl From<ConfigAccountV0> for ConfigAccount {
+    fn from(v0: ConfigAccountV0) -> Self {
+        // only the protocol config existed, and its judge held every role
+        Self {
+            version: CONFIG_VERSION,
+            namespace: DEFAULT_NAMESPACE,
+            admin: v0.judge,
+            judge: v0.judge,
+            guardian: v0.judge,
+            treasury: v0.treasury,
+            pending_admin: None,
+            pending_judge: v0.pending_judge,
+            tax: v0.tax,
+            fee: v0.fee as u16 * 100,
+            judge_share: 0,
+            timelock: DEFAULT_CONFIG_TIMELOCK,
+            queued_update: None,
+            queued_eta: 0,
+            paused: 0,
+            bump: v0.bump,
+        }
+    }
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", payer.key().as_ref()],
-        bump = escrow.bump,
-        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
-        constraint = escrow.payer == payer.key() @ ErrorCode::NotPayerReturning,
-        constraint = escrow.payee == payee.key() @ ErrorCode::NotPayeeReturning,
-        constraint = !escrow.disputed @ ErrorCode::EscrowDisputed,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+// Before versioning - the launch layout, identified by account size since it has no version byte
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct EscrowAccountV0 {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub amount: u64,
+    pub tax: u16,
+    pub fee: u8, // percentage, not BPS
+    pub token_mint: Option<Pubkey>,
+    pub disputed: bool,
+    pub deadline: i64,
+    pub judge_deadline: i64,
+    pub creation_time: i64,
+    pub bump: u8,
+}
 
-    pub system_program: Program<'info, System>
+impl From<EscrowAccountV0> for EscrowAccount {
+    fn from(v0: EscrowAccountV0) -> Self {
+        Self {
+            version: ESCROW_VERSION,
+            config: default_config_address(), // launch escrows all answered to the protocol config
+            payer: v0.payer,
+            payee: v0.payee,
+            amount: v0.amount,
+            deposited: 0, // backfilled by migrate_escrow
+            tax: v0.tax,
+            fee: v0.fee as u16 * 100,
+            // nothing was agreed on, so these settle at the plain BPS fee
+            min_fee: 0,
+            max_fee: u64::MAX,
+            token_mint: v0.token_mint,
+            legs: Vec::new(),
+            nft: false,
+            counter: None,
+            vesting: None,
+            recurring: None,
+            payees: Vec::new(),
+            approver: None,
+            disputed: v0.disputed,
+            deadline: v0.deadline,
+            judge_deadline: v0.judge_deadline,
+            creation_time: v0.creation_time,
+            bump: v0.bump,
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct JudgeSolanaContext<'info> {
-    #[account(mut)]
-    pub judge: Signer<'info>,
+// The layout version stored in raw account data, 0 for pre-versioning accounts
+fn layout_version(data: &[u8], discriminator: &[u8], v0_space: usize) -> Result<u8> {
+    if !data.starts_with(discriminator) {
+        return Err(error!(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch));
+    }
+    if data.len() == 8 + v0_space {
+        return Ok(0);
+    }
+    data.get(8).copied().ok_or_else(|| error!(ErrorCode::UnknownAccountVersion))
+}
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payee: AccountInfo<'info>,
+impl ConfigAccount {
+    pub fn from_legacy(data: &[u8]) -> Result<(u8, Self)> {
+        let version = layout_version(data, ConfigAccount::DISCRIMINATOR, ConfigAccountV0::INIT_SPACE)?;
+        let mut body = &data[8..];
+        let config = match version {
+            0 => ConfigAccountV0::deserialize(&mut body)?.into(),
+            CONFIG_VERSION => return Err(error!(ErrorCode::AlreadyMigrated)),
+            _ => return Err(error!(ErrorCode::UnknownAccountVersion)),
+        };
+        Ok((version, config))
+    }
+}
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payer: AccountInfo<'info>,
+impl EscrowAccount {
+    pub fn from_legacy(data: &[u8]) -> Result<(u8, Self)> {
+        let version = layout_version(data, EscrowAccount::DISCRIMINATOR, EscrowAccountV0::INIT_SPACE)?;
+        let mut body = &data[8..];
+        let escrow = match version {
+            0 => EscrowAccountV0::deserialize(&mut body)?.into(),
+            ESCROW_VERSION => return Err(error!(ErrorCode::AlreadyMigrated)),
+            _ => return Err(error!(ErrorCode::UnknownAccountVersion)),
+        };
+        Ok((version, escrow))
+    }
+}
 
-    /// CHECK: This is treasury pubkey
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
+trait WriteMigrated: AccountSerialize {
+    // wipe the old bytes (stale tails and all) and write the current layout
+    fn write_migrated(&self, info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        data.fill(0);
+        self.try_serialize(&mut &mut data[..])
+    }
+}
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
-        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
-    )]
-    pub config: Account<'info, ConfigAccount>,
+impl WriteMigrated for ConfigAccount {}
+impl WriteMigrated for EscrowAccount {}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.payer.as_ref()],
-        bump = escrow.bump,
-        constraint = !escrow.token_mint.is_some() @ ErrorCode::EscrowNotSolana,
-        constraint = escrow.disputed @ ErrorCode::EscrowNotDisputed,
-        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
-        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+fn default_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &crate::ID).0
+}
 
-    pub system_program: Program<'info, System>
+fn realloc_for_migration<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    // the signer covers the extra rent, whatever the account already holds stays escrowed
+    let rent = Rent::get()?;
+    let rent_due = rent.minimum_balance(new_len).saturating_sub(rent.minimum_balance(info.data_len()));
+    if rent_due > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    info.realloc(new_len, false)?;
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct JudgeTokenContext<'info> {
-    #[account(mut)]
-    pub judge: Signer<'info>,
+// ========================================================================= //
+// Events                                                                    //
+//   ▄████████   ▄█    █▄     ▄████████ ███▄▄▄▄       ███        ▄████████   //
+//   ███    ███ ███    ███   ███    ███ ███▀▀▀██▄ ▀█████████▄   ███    ███   //
+//   ███    █▀  ███    ███   ███    █▀  ███   ███    ▀███▀▀██   ███    █▀    //
+//  ▄███▄▄▄     ███    ███  ▄███▄▄▄     ███   ███     ███   ▀   ███          //
+// ▀▀███▀▀▀     ███    ███ ▀▀███▀▀▀     ███   ███     ███     ▀███████████   //
+//   ███    █▄  ███    ███   ███    █▄  ███   ███     ███              ███   //
+//   ███    ███ ███    ███   ███    ███ ███   ███     ███        ▄█    ███   //
+//   ██████████  ▀██████▀    ██████████  ▀█   █▀     ▄████▀    ▄████████▀    //
+// ========================================================================  // 
+                                                      
+#[event]
+pub struct ConfigCreated {
+    pub address: Pubkey,
+    pub namespace: Pubkey,
+    pub treasury: Pubkey,
+    pub admin: Pubkey,
+    pub judge: Pubkey,
+    pub tax: u16,
+    pub fee: u16,
+    pub timestamp: i64,
+}
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payee: AccountInfo<'info>,
+#[event]
+pub struct ConfigUpdated {
+    pub address: Pubkey,
+    pub treasury: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub pending_judge: Option<Pubkey>,
+    pub tax: u16,
+    pub fee: u16,
+    pub judge_share: u16,
+    pub timelock: i64,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
 
-    /// CHECK: This is payee pubkey
-    #[account(mut)]
-    pub payer: AccountInfo<'info>,
+#[event]
+pub struct PauseUpdated {
+    pub address: Pubkey,
+    pub paused: u8,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
 
-    /// CHECK: This is treasury pubkey
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
+#[event]
+pub struct ConfigUpdateQueued {
+    pub address: Pubkey,
+    pub updates: ConfigUpdateArgs,
+    pub eta: i64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump,
-        constraint = config.treasury == treasury.key() @ ErrorCode::UninvolvedUser,
-        constraint = config.judge == judge.key() @ ErrorCode::UninvolvedUser,
-    )]
-    pub config: Account<'info, ConfigAccount>,
+#[event]
+pub struct ConfigUpdateCancelled {
+    pub address: Pubkey,
+    pub updates: ConfigUpdateArgs,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.payer.as_ref()],
-        bump = escrow.bump,
-        constraint = escrow.token_mint.is_some() @ ErrorCode::EscrowNotToken,
-        constraint = escrow.disputed @ ErrorCode::EscrowNotDisputed,
-        constraint = escrow.payee == payee.key() @ ErrorCode::UninvolvedUser,
-        constraint = escrow.payer == payer.key() @ ErrorCode::UninvolvedUser,
-        close = payer,
-    )]
-    pub escrow: Account<'info, EscrowAccount>,
+#[event]
+pub struct JudgeNominated {
+    pub address: Pubkey,
+    pub pending_judge: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = escrow.token_mint == Some(mint_account.key()) @ ErrorCode::WrongToken,
-    )]
-    pub mint_account: Account<'info, Mint>,
+#[event]
+pub struct JudgeAccepted {
+    pub address: Pubkey,
+    pub old_judge: Pubkey,
+    pub new_judge: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = payer_token_account.mint == mint_account.key(),
-        constraint = payer_token_account.owner == payer.key(),
-    )]
-    pub payer_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct AdminNominated {
+    pub address: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = payee_token_account.mint == mint_account.key(),
-        constraint = payee_token_account.owner == payee.key(),
-    )]
-    pub payee_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct AdminAccepted {
+    pub address: Pubkey,
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        constraint = treasury_token_account.mint == mint_account.key(),
-        constraint = treasury_token_account.owner == treasury.key(),
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct TaxTiersUpdated {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub tiers: Vec<TaxTier>,
+    pub timestamp: i64,
+}
 
-    #[account(
-        associated_token::mint = mint_account,
-        associated_token::authority = escrow,
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct FeeBoundsUpdated {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub min_fee: u64,
+    pub max_fee: u64,
+    pub timestamp: i64,
+}
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
+#[event]
+pub struct MintAllowed {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
 }
-// ================================================================================================================================  //
-// Arg Structs - Function Argument Definitions                                                                                       //
-//   ▄████████    ▄████████    ▄██████▄          ▄████████     ███        ▄████████ ███    █▄   ▄████████     ███        ▄████████   // 
-//   ███    ███   ███    ███   ███    ███        ███    ███ ▀█████████▄   ███    ███ ███    ███ ███    ███ ▀█████████▄   ███    ███  // 
-//   ███    ███   ███    ███   ███    █▀         ███    █▀     ▀███▀▀██   ███    ███ ███    ███ ███    █▀     ▀███▀▀██   ███    █▀   // 
-//   ███    ███  ▄███▄▄▄▄██▀  ▄███               ███            ███   ▀  ▄███▄▄▄▄██▀ ███    ███ ███            ███   ▀   ███         // 
-// ▀███████████ ▀▀███▀▀▀▀▀   ▀▀███ ████▄       ▀███████████     ███     ▀▀███▀▀▀▀▀   ███    ███ ███            ███     ▀███████████  // 
-//   ███    ███ ▀███████████   ███    ███               ███     ███     ▀███████████ ███    ███ ███    █▄      ███              ███  // 
-//   ███    ███   ███    ███   ███    ███         ▄█    ███     ███       ███    ███ ███    ███ ███    ███     ███        ▄█    ███  // 
-//   ███    █▀    ███    ███   ████████▀        ▄████████▀     ▄████▀     ███    ███ ████████▀  ████████▀     ▄████▀    ▄████████▀   // 
-//                ███    ███                                              ███    ███                                                 // 
-// ================================================================================================================================  //
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct ConfigUpdateArgs {
-    pub treasury: Option<Pubkey>,
-    pub pending_judge: Option<Pubkey>,
-    pub tax: Option<u16>,
-    pub fee: Option<u8>,
+#[event]
+pub struct MintRemoved {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct EscrowCreationArgs {
-    pub amount: u64,
-    pub payee: Pubkey,
-    pub deadline: i64,
-    pub judge_deadline: i64,
-    pub token_mint: Option<Pubkey>
+#[event]
+pub struct AccountMigrated {
+    pub address: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
 }
 
-// ========================================================================================================== //
-// Account Definitions                                                                                        //
-//   ▄████████  ▄████████  ▄████████     ███          ████████▄     ▄████████    ▄████████    ▄████████       //
-//   ███    ███ ███    ███ ███    ███ ▀█████████▄      ███   ▀███   ███    ███   ███    ███   ███    ███      //
-//   ███    ███ ███    █▀  ███    █▀     ▀███▀▀██      ███    ███   ███    █▀    ███    █▀    ███    █▀       //
-//   ███    ███ ███        ███            ███   ▀      ███    ███  ▄███▄▄▄      ▄███▄▄▄       ███             //
-// ▀███████████ ███        ███            ███          ███    ███ ▀▀███▀▀▀     ▀▀███▀▀▀     ▀███████████      //
-//   ███    ███ ███    █▄  ███    █▄      ███          ███    ███   ███    █▄    ███                 ███      //
-//   ███    ███ ███    ███ ███    ███     ███          ███   ▄███   ███    ███   ███           ▄█    ███      //
-//   ███    █▀  ████████▀  ████████▀     ▄████▀        ████████▀    ██████████   ███         ▄████████▀       //
-// ========================================================================================================== //     
+#[event]
+pub struct EscrowCreated {
+    pub address: Pubkey,
+    pub config: Pubkey,
+    pub payer: Pubkey,             
+    pub payee: Pubkey,             
+    pub amount: u64,               
+    pub token_mint: Option<Pubkey>,
+    pub timestamp: i64,        
+}
+
+#[event]
+pub struct EscrowDeposited {
+    pub address: Pubkey,
+    pub amount: u64,
+    pub token_mint: Option<Pubkey>,
+    pub timestamp: i64,
+}
 
+#[event]
+pub struct AssetLegAdded {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
 
-                                                                                                    
+#[event]
+pub struct VestingSet {
+    pub address: Pubkey,
+    pub steps: u16,
+    pub timestamp: i64,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct ConfigAccount {
-    pub judge: Pubkey,
-    pub treasury: Pubkey,
-    pub pending_judge: Option<Pubkey>,
-    pub tax: u16, // BPS Fee for all future transactions
-    pub fee: u8, // Incentivize the DAO to rule on escrows
-    pub bump: u8, // Store the bump for verification later
+#[event]
+pub struct VestedClaimed {
+    pub address: Pubkey,
+    pub amount: u64,
+    pub tax_paid: u64,
+    pub claimed: u64,
+    pub token_mint: Option<Pubkey>,
+    pub timestamp: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct EscrowAccount {
-    pub payer: Pubkey,              // The person depositing funds
-    pub payee: Pubkey,              // The recipient who should receive funds
-    pub amount: u64,                // Amount held in escrow
-    pub tax: u16,                   // the tax at time of escrow creation, ie the tax amount Payer and Payee agreed to when escrow was created. BPS.
-    pub fee: u8,                    // the fee at time of escrow creation, ie the fee amount Payer and Payee agreed to when escrow was created. Percentage.
-    pub token_mint: Option<Pubkey>, // If None, this is a SOL escrow, otherwise an SPL token
-    pub disputed: bool,             
-    pub deadline: i64,              // judge has to wait til after this time to raise a dispute
-    pub judge_deadline: i64,
-    pub creation_time: i64,         // When escrow was created (unix timestamp)
-    pub bump: u8,                   // Bump for PDA verification
+#[event]
+pub struct RecurringSet {
+    pub address: Pubkey,
+    pub periods: u16,
+    pub interval: i64,
+    pub auto_release: bool,
+    pub timestamp: i64,
 }
 
-// ========================================================================= //
-// Events                                                                    //
-//   ▄████████   ▄█    █▄     ▄████████ ███▄▄▄▄       ███        ▄████████   //
-//   ███    ███ ███    ███   ███    ███ ███▀▀▀██▄ ▀█████████▄   ███    ███   //
-//   ███    █▀  ███    ███   ███    █▀  ███   ███    ▀███▀▀██   ███    █▀    //
-//  ▄███▄▄▄     ███    ███  ▄███▄▄▄     ███   ███     ███   ▀   ███          //
-// ▀▀███▀▀▀     ███    ███ ▀▀███▀▀▀     ███   ███     ███     ▀███████████   //
-//   ███    █▄  ███    ███   ███    █▄  ███   ███     ███              ███   //
-//   ███    ███ ███    ███   ███    ███ ███   ███     ███        ▄█    ███   //
-//   ██████████  ▀██████▀    ██████████  ▀█   █▀     ▄████▀    ▄████████▀    //
-// ========================================================================  // 
-                                                      
 #[event]
-pub struct ConfigCreated {
+pub struct PeriodReleased {
     pub address: Pubkey,
-    pub treasury: Pubkey,
-    pub judge: Pubkey,
-    pub tax: u16,
-    pub fee: u8,
+    pub period: u16,
+    pub amount: u64,
+    pub tax_paid: u64,
+    pub token_mint: Option<Pubkey>,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct ConfigUpdated {
+pub struct RecurringCancelled {
     pub address: Pubkey,
-    pub treasury: Pubkey,
-    pub pending_judge: Option<Pubkey>,
-    pub tax: u16,
-    pub fee: u8,
+    pub paid_to_payee: u64,
+    pub tax_paid: u64,
+    pub refunded: u64,
+    pub token_mint: Option<Pubkey>,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct JudgeNominated {
+pub struct PayeesSet {
     pub address: Pubkey,
-    pub pending_judge: Pubkey,
+    pub payees: Vec<Pubkey>,
+    pub shares: Vec<u16>,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct JudgeAccepted {
+pub struct ApproverSet {
     pub address: Pubkey,
-    pub old_judge: Pubkey,
-    pub new_judge: Pubkey,
+    pub old_approver: Option<Pubkey>,
+    pub new_approver: Option<Pubkey>,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct EscrowCreated {
+pub struct CounterLegAdded {
     pub address: Pubkey,
-    pub payer: Pubkey,             
-    pub payee: Pubkey,             
-    pub amount: u64,               
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwapSettled {
+    pub address: Pubkey,
+    pub executed: bool,
+    pub judged: bool,
+    pub amount: u64,
+    pub fees_paid: u64,
+    pub counter_amount: u64,
+    pub counter_fees_paid: u64,
     pub token_mint: Option<Pubkey>,
-    pub timestamp: i64,        
+    pub counter_mint: Pubkey,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct EscrowDeposited {
+pub struct EscrowLegSettled {
     pub address: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
     pub amount: u64,
+    pub fees_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowAmended {
+    pub address: Pubkey,
+    pub old_amount: u64,
+    pub new_amount: u64,
+    pub refunded: u64,
     pub token_mint: Option<Pubkey>,
     pub timestamp: i64,
 }
@@ -1216,6 +5463,9 @@ pub struct EscrowJudged {
     pub winner: Pubkey,
     pub amount_awarded: u64,
     pub fee_collected: u64,
+    pub fee_bps: u16,
+    pub judge_paid: u64,
+    pub treasury_paid: u64,
     pub token_mint: Option<Pubkey>,
     pub timestamp: i64,
 }
@@ -1238,13 +5488,19 @@ pub enum ErrorCode {
     #[msg("Tax rate exceeds maximum of 2000 basis points (20%)")]
     TaxTooHigh,
     
-    #[msg("Judge fee exceeds maximum of 20%")]
+    #[msg("Judge fee exceeds maximum of 2000 basis points (20%)")]
     FeeTooHigh,
     
     #[msg("Invalid treasury address")]
     InvalidTreasury,
+
+    #[msg("Program data account does not belong to this program")]
+    InvalidProgramData,
+
+    #[msg("Unauthorized: Only the program upgrade authority can initialize")]
+    NotUpgradeAuthority,
     
-    #[msg("Unauthorized: Only the judge can perform this action")]
+    #[msg("Unauthorized: Only the config admin can perform this action")]
     UnauthorizedConfigOwner,
     
     #[msg("Unauthorized: Only the judge can perform this action")]
@@ -1252,6 +5508,9 @@ pub enum ErrorCode {
     
     #[msg("Config account does not match the expected PDA")]
     InvalidConfigAccount,
+
+    #[msg("Operation failed - escrow belongs to a different config")]
+    WrongConfig,
     
     #[msg("Operation would result in insufficient funds")]
     InsufficientFunds,
@@ -1265,6 +5524,12 @@ pub enum ErrorCode {
     #[msg("Signer is not current nominee")]
     UnauthorizedJudge,
 
+    #[msg("Operation failed - can not accept admin seat unless nominated - no current nominee")]
+    NoPendingAdmin,
+
+    #[msg("Signer is not current admin nominee")]
+    UnauthorizedAdmin,
+
     #[msg("Operation Failed - Escrow not in recoverable state, wait for judge deadline to pass.")]
     RecoverTooEarly,
 
@@ -1299,5 +5564,789 @@ pub enum ErrorCode {
     EscrowDisputed,
 
     #[msg("Operation failed - Escrow is not in Dispute")]
-    EscrowNotDisputed
-}
\ No newline at end of file
+    EscrowNotDisputed,
+
+    #[msg("Too many tax tiers")]
+    TooManyTaxTiers,
+
+    #[msg("Tax tiers must have ascending thresholds and non-increasing taxes")]
+    InvalidTaxTiers,
+
+    #[msg("Minimum judge fee can not exceed the maximum")]
+    InvalidFeeBounds,
+
+    #[msg("Judge share exceeds 10000 basis points (100%)")]
+    JudgeShareTooHigh,
+
+    #[msg("Escrow creation failed - token mint is not on the allowlist")]
+    MintNotAllowed,
+
+    #[msg("Mint has a freeze authority and can not be allowed")]
+    MintHasFreezeAuthority,
+
+    #[msg("Operation failed - a config update is already queued, cancel it first")]
+    ConfigUpdateAlreadyQueued,
+
+    #[msg("Operation failed - no config update is queued")]
+    NoQueuedConfigUpdate,
+
+    #[msg("Operation failed - queued config update is still timelocked")]
+    ConfigUpdateTimelocked,
+
+    #[msg("Timelock must be between 0 and 30 days")]
+    InvalidTimelock,
+
+    #[msg("Operation failed - this instruction is paused")]
+    ProgramPaused,
+
+    #[msg("Unauthorized: Only the admin or guardian can pause")]
+    UnauthorizedPauser,
+
+    #[msg("Guardian can only add pause flags, the admin has to unpause")]
+    GuardianCannotUnpause,
+
+    #[msg("Unknown pause flags")]
+    InvalidPauseFlags,
+
+    #[msg("Operation failed - account is already on the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Operation failed - unknown account layout version")]
+    UnknownAccountVersion,
+
+    #[msg("Operation failed - escrow is already funded")]
+    EscrowAlreadyFunded,
+
+    #[msg("Operation failed - escrow has not been funded yet")]
+    EscrowNotFunded,
+
+    #[msg("Operation failed - amended amount must be lower than the current amount")]
+    InvalidAmendment,
+
+    #[msg("Operation failed - token escrows need the payer and escrow token accounts")]
+    MissingTokenAccounts,
+
+    #[msg("Operation failed - escrow is not a wrapped SOL escrow")]
+    EscrowNotWrappedSol,
+
+    #[msg("Too many asset legs")]
+    TooManyAssetLegs,
+
+    #[msg("Operation failed - escrow already holds this mint")]
+    DuplicateAssetLeg,
+
+    #[msg("Operation failed - multi-asset escrows settle through release, return_funds, judge and recover")]
+    EscrowHasAssetLegs,
+
+    #[msg("Operation failed - asset leg vault is not the escrow's associated token account")]
+    InvalidLegVault,
+
+    #[msg("Operation failed - mint is not a non-fungible token (0 decimals, supply of 1)")]
+    NotAnNft,
+
+    #[msg("Operation failed - NFT escrows always hold exactly one token")]
+    NftAmountFixed,
+
+    #[msg("Operation failed - NFT metadata does not match the mint or collection")]
+    InvalidNftMetadata,
+
+    #[msg("Operation failed - escrow is not a swap")]
+    NotASwap,
+
+    #[msg("Operation failed - swaps settle through settle_swap, cancel_swap or judge_swap")]
+    SwapEscrow,
+
+    #[msg("Operation failed - escrow already has a counter leg")]
+    CounterLegAlreadySet,
+
+    #[msg("Operation failed - both sides of the swap have to be funded")]
+    SwapNotFunded,
+
+    #[msg("Operation failed - swap deadline has passed")]
+    SwapExpired,
+
+    #[msg("Operation failed - swap can't be cancelled yet")]
+    SwapStillOpen,
+
+    #[msg("Operation failed - escrow does not vest")]
+    NotVesting,
+
+    #[msg("Operation failed - vesting escrows can't have asset legs, a counter leg or an NFT")]
+    VestingIncompatible,
+
+    #[msg("Operation failed - vesting escrows settle through claim_vested and the unified instructions")]
+    VestingEscrow,
+
+    #[msg("Operation failed - nothing has vested since the last claim")]
+    NothingVested,
+
+    #[msg("Operation failed - payee still has vested funds to claim")]
+    VestedUnclaimed,
+
+    #[msg("Operation failed - escrow is not recurring")]
+    NotRecurring,
+
+    #[msg("Operation failed - recurring escrows can't have asset legs, a counter leg, an NFT or vesting")]
+    RecurringIncompatible,
+
+    #[msg("Operation failed - recurring escrows settle period by period or through the unified instructions")]
+    RecurringEscrow,
+
+    #[msg("Operation failed - recurring escrows need at least one period and a positive interval")]
+    InvalidRecurrence,
+
+    #[msg("Operation failed - next period hasn't unlocked yet")]
+    PeriodLocked,
+
+    #[msg("Operation failed - payee shares need 2 to 5 distinct payees including the escrow's payee, adding up to 10000 bps")]
+    InvalidPayeeShares,
+
+    #[msg("Operation failed - split escrows can't have asset legs, a counter leg, an NFT, vesting or recurring periods")]
+    SplitIncompatible,
+
+    #[msg("Operation failed - split escrows settle through the unified and per-share instructions")]
+    SplitEscrow,
+
+    #[msg("Operation failed - signer is not one of the escrow's payees")]
+    NotAPayee,
+
+    #[msg("Operation failed - share has already been settled")]
+    ShareSettled,
+
+    #[msg("Operation failed - a payee's share is disputed")]
+    ShareDisputed,
+
+    #[msg("Operation failed - share is not disputed")]
+    ShareNotDisputed,
+
+    #[msg("Operation failed - signer is not the escrow's approver")]
+    NotApprover,
+
+    #[msg("Operation failed - a payee can't approve their own release")]
+    InvalidApprover,
+
+    #[msg("Operation failed - a mint policy change has to be queued on its own")]
+    MixedConfigUpdate,
+
+    #[msg("Operation failed - queued update is a mint policy change, apply it with its own instruction")]
+    MintPolicyQueued,
+
+    #[msg("Operation failed - doesn't match the queued mint policy change")]
+    MintPolicyMismatch,
+
+    #[msg("Operation failed - wSOL escrows only pay out in one go, so no vesting, recurring periods, splits or refunds")]
+    WrappedSolPartialPayout,
+
+    #[msg("Operation failed - judge deadline has passed, the payer can recover instead")]
+    JudgeDeadlinePassed,
+
+    #[msg("Unauthorized: Only the admin or guardian can remove a mint")]
+    UnauthorizedMintRemoval,
+
+    #[msg("Operation failed - a leg recipient's token account doesn't exist yet, create it first")]
+    LegTokenAccountMissing
+}
+
+#[cfg(test)]
+mod migration_tests {
+    use super::*;
+
+    // discriminator + borsh body, padded out to the account size like a real allocation
+    fn legacy_bytes<T: AnchorSerialize>(discriminator: &[u8], body: &T, space: usize, padding: u8) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        body.serialize(&mut data).unwrap();
+        data.resize(8 + space, padding);
+        data
+    }
+
+    fn config_v0() -> ConfigAccountV0 {
+        ConfigAccountV0 {
+            judge: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            pending_judge: Some(Pubkey::new_unique()),
+            tax: 50,
+            fee: 5,
+            bump: 254,
+        }
+    }
+
+    fn escrow_v0() -> EscrowAccountV0 {
+        EscrowAccountV0 {
+            payer: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            amount: 5_000_000,
+            tax: 50,
+            fee: 5,
+            token_mint: Some(Pubkey::new_unique()),
+            disputed: true,
+            deadline: 1_700_000_000,
+            judge_deadline: 1_702_419_200,
+            creation_time: 1_698_790_400,
+            bump: 253,
+        }
+    }
+
+    #[test]
+    fn migrates_v0_config() {
+        let old = config_v0();
+        // stale bytes past the borsh body must not leak into the new layout
+        let data = legacy_bytes(ConfigAccount::DISCRIMINATOR, &old, ConfigAccountV0::INIT_SPACE, 0xAB);
+        let (from_version, config) = ConfigAccount::from_legacy(&data).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.namespace, DEFAULT_NAMESPACE);
+        assert_eq!(config.admin, old.judge);
+        assert_eq!(config.judge, old.judge);
+        assert_eq!(config.guardian, old.judge);
+        assert_eq!(config.treasury, old.treasury);
+        assert_eq!(config.pending_admin, None);
+        assert_eq!(config.pending_judge, old.pending_judge);
+        assert_eq!(config.tax, old.tax);
+        assert_eq!(config.fee, 500);
+        assert_eq!(config.judge_share, 0);
+        assert_eq!(config.timelock, DEFAULT_CONFIG_TIMELOCK);
+        assert!(config.queued_update.is_none());
+        assert_eq!(config.paused, 0);
+        assert_eq!(config.bump, old.bump);
+    }
+
+    #[test]
+    fn migrates_v0_escrow() {
+        let old = escrow_v0();
+        let data = legacy_bytes(EscrowAccount::DISCRIMINATOR, &old, EscrowAccountV0::INIT_SPACE, 0);
+        let (from_version, escrow) = EscrowAccount::from_legacy(&data).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(escrow.version, ESCROW_VERSION);
+        assert_eq!(escrow.config, Pubkey::find_program_address(&[b"config"], &ID).0);
+        assert_eq!(escrow.payer, old.payer);
+        assert_eq!(escrow.payee, old.payee);
+        assert_eq!(escrow.amount, old.amount);
+        assert_eq!(escrow.deposited, 0);
+        assert_eq!(escrow.tax, old.tax);
+        assert_eq!(escrow.fee, 500);
+        assert_eq!(escrow.token_mint, old.token_mint);
+        assert_eq!(escrow.disputed, old.disputed);
+        assert_eq!(escrow.deadline, old.deadline);
+        assert_eq!(escrow.judge_deadline, old.judge_deadline);
+        assert_eq!(escrow.creation_time, old.creation_time);
+        assert_eq!(escrow.bump, old.bump);
+        // everything added since launch comes up empty
+        assert_eq!((escrow.min_fee, escrow.max_fee), (0, u64::MAX));
+        assert_eq!(escrow.judge_fee(1_000_000), escrow.fee_due(1_000_000));
+        assert!(escrow.legs.is_empty() && !escrow.nft && !escrow.is_swap());
+        assert!(escrow.vesting.is_none() && escrow.recurring.is_none());
+        assert!(escrow.payees.is_empty() && escrow.approver.is_none());
+    }
+
+    #[test]
+    fn migrates_launch_bytes() {
+        // laid out by hand, field for field, the way the launch build wrote them
+        let judge = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let mut data = ConfigAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(judge.as_ref());
+        data.extend_from_slice(treasury.as_ref());
+        data.push(0); // pending_judge: None, borsh packs it so the slack ends up at the tail
+        data.extend_from_slice(&250u16.to_le_bytes());
+        data.push(3);
+        data.push(255);
+        data.resize(8 + ConfigAccountV0::INIT_SPACE, 0);
+        let (from_version, config) = ConfigAccount::from_legacy(&data).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!((config.judge, config.treasury, config.pending_judge), (judge, treasury, None));
+        assert_eq!((config.tax, config.fee, config.bump), (250, 300, 255));
+
+        let payer = Pubkey::new_unique();
+        let payee = Pubkey::new_unique();
+        let mut data = EscrowAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(payer.as_ref());
+        data.extend_from_slice(payee.as_ref());
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&100u16.to_le_bytes());
+        data.push(2);
+        data.push(0); // token_mint: None, SOL escrow
+        data.push(0);
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&1_702_419_200i64.to_le_bytes());
+        data.extend_from_slice(&1_698_790_400i64.to_le_bytes());
+        data.push(252);
+        data.resize(8 + EscrowAccountV0::INIT_SPACE, 0);
+        let (from_version, escrow) = EscrowAccount::from_legacy(&data).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!((escrow.payer, escrow.payee, escrow.amount), (payer, payee, 1_000_000));
+        assert_eq!((escrow.tax, escrow.fee, escrow.token_mint), (100, 200, None));
+        assert_eq!((escrow.deadline, escrow.creation_time, escrow.bump), (1_700_000_000, 1_698_790_400, 252));
+    }
+
+    #[test]
+    fn migrated_account_reads_back_as_current_layout() {
+        let data = legacy_bytes(EscrowAccount::DISCRIMINATOR, &escrow_v0(), EscrowAccountV0::INIT_SPACE, 0xFF);
+        let (_, escrow) = EscrowAccount::from_legacy(&data).unwrap();
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut buffer = vec![0xFF; 8 + EscrowAccount::INIT_SPACE];
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut buffer, &ID, false, 0);
+        escrow.write_migrated(&info).unwrap();
+
+        let reread = EscrowAccount::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap();
+        assert_eq!(reread.version, ESCROW_VERSION);
+        assert_eq!(reread.payer, escrow.payer);
+        assert_eq!(reread.creation_time, escrow.creation_time);
+        // nothing stale left behind the new body
+        let body_len = 8 + escrow.try_to_vec().unwrap().len();
+        assert!(info.try_borrow_data().unwrap()[body_len..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn rejects_current_layout() {
+        let mut config = ConfigAccount::from(config_v0());
+        config.version = CONFIG_VERSION;
+        let mut data = Vec::new();
+        config.try_serialize(&mut data).unwrap();
+        data.resize(8 + ConfigAccount::INIT_SPACE, 0);
+        let Err(err) = ConfigAccount::from_legacy(&data) else {
+            panic!("current layout should not migrate");
+        };
+        assert_eq!(err, error!(ErrorCode::AlreadyMigrated));
+    }
+
+    #[test]
+    fn rejects_foreign_accounts() {
+        // an escrow is not a config, whatever its size
+        let data = legacy_bytes(EscrowAccount::DISCRIMINATOR, &escrow_v0(), ConfigAccountV0::INIT_SPACE, 0);
+        assert!(ConfigAccount::from_legacy(&data).is_err());
+    }
+
+    #[test]
+    fn default_namespace_keeps_original_config_address() {
+        let mut config = ConfigAccount::from(config_v0());
+        config.namespace = DEFAULT_NAMESPACE;
+        let (default_config, _) = Pubkey::find_program_address(&[b"config"], &ID);
+        assert_eq!(Pubkey::find_program_address(&[b"config", config.namespace_seed()], &ID).0, default_config);
+
+        config.namespace = Pubkey::new_unique();
+        assert_ne!(Pubkey::find_program_address(&[b"config", config.namespace_seed()], &ID).0, default_config);
+    }
+}
+
+#[cfg(test)]
+mod escrow_tests {
+    use super::*;
+
+    // a funded 1_000_000 unit escrow, 1% tax and 5% judge fee, deadline 1_000s after creation
+    fn escrow() -> EscrowAccount {
+        EscrowAccount {
+            version: ESCROW_VERSION,
+            config: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            amount: 1_000_000,
+            deposited: 1_000_000,
+            tax: 100,
+            fee: 500,
+            min_fee: 0,
+            max_fee: u64::MAX,
+            token_mint: Some(Pubkey::new_unique()),
+            legs: Vec::new(),
+            nft: false,
+            counter: None,
+            vesting: None,
+            recurring: None,
+            payees: Vec::new(),
+            approver: None,
+            disputed: false,
+            deadline: 2_000,
+            judge_deadline: 5_000,
+            creation_time: 1_000,
+            bump: 255,
+        }
+    }
+
+    // the protocol config, 1% tax, 5% judge fee and half of it to the judge, nothing queued or paused
+    fn config() -> ConfigAccount {
+        let admin = Pubkey::new_unique();
+        ConfigAccount {
+            version: CONFIG_VERSION,
+            namespace: DEFAULT_NAMESPACE,
+            admin,
+            judge: Pubkey::new_unique(),
+            guardian: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            pending_admin: None,
+            pending_judge: None,
+            tax: 100,
+            fee: 500,
+            judge_share: 5000,
+            timelock: DEFAULT_CONFIG_TIMELOCK,
+            queued_update: None,
+            queued_eta: 0,
+            paused: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn swap_funding_closes_at_deadline() {
+        let mut escrow = escrow();
+        assert!(!escrow.swap_expired(3_000));
+        escrow.counter = Some(AssetLeg { mint: Pubkey::new_unique(), amount: 10, deposited: 0 });
+        assert!(!escrow.swap_expired(2_000));
+        assert!(escrow.swap_expired(2_001));
+    }
+
+    #[test]
+    fn swap_cancel_waits_for_the_judge_once_both_sides_funded() {
+        let mut escrow = escrow();
+        escrow.counter = Some(AssetLeg { mint: Pubkey::new_unique(), amount: 10, deposited: 0 });
+        // only the payer funded, the payee missed the deadline
+        assert_eq!(escrow.swap_open_until(), escrow.deadline);
+        escrow.counter.as_mut().unwrap().deposited = 10;
+        assert_eq!(escrow.swap_open_until(), escrow.judge_deadline);
+        escrow.deposited = 0;
+        assert_eq!(escrow.swap_open_until(), escrow.deadline);
+        escrow.disputed = true;
+        assert_eq!(escrow.swap_open_until(), escrow.judge_deadline);
+    }
+
+    #[test]
+    fn vesting_unlocks_linearly_or_in_steps() {
+        let mut escrow = escrow();
+        escrow.vesting = Some(Vesting { steps: 0, claimed: 0, frozen_at: 0 });
+        assert_eq!(escrow.vested_unclaimed(900), 0);
+        assert_eq!(escrow.vested_unclaimed(1_500), 500_000);
+        assert_eq!(escrow.vested_unclaimed(3_000), 1_000_000);
+
+        escrow.vesting = Some(Vesting { steps: 4, claimed: 0, frozen_at: 0 });
+        assert_eq!(escrow.vested_unclaimed(1_249), 0);
+        assert_eq!(escrow.vested_unclaimed(1_499), 250_000);
+        assert_eq!(escrow.vested_unclaimed(1_500), 500_000);
+        assert_eq!(escrow.vested_unclaimed(2_000), 1_000_000);
+    }
+
+    #[test]
+    fn vesting_nets_out_claims_and_stops_at_dispute() {
+        let mut escrow = escrow();
+        // 250_000 already claimed, so the schedule still runs over the full 1_000_000
+        escrow.deposited = 750_000;
+        escrow.vesting = Some(Vesting { steps: 0, claimed: 250_000, frozen_at: 0 });
+        assert_eq!(escrow.vested_unclaimed(1_200), 0);
+        assert_eq!(escrow.vested_unclaimed(1_500), 250_000);
+        assert_eq!(escrow.vested_unclaimed(3_000), 750_000);
+
+        escrow.vesting = Some(Vesting { steps: 0, claimed: 250_000, frozen_at: 1_600 });
+        assert_eq!(escrow.vested_unclaimed(1_500), 250_000);
+        assert_eq!(escrow.vested_unclaimed(3_000), 350_000);
+    }
+
+    #[test]
+    fn top_up_deposits_what_it_adds() {
+        let mut escrow = escrow();
+        assert_eq!(escrow.top_up(500).unwrap(), 1_000_000);
+        assert_eq!(escrow.amount, 1_000_500);
+        assert_eq!(escrow.deposited, 1_000_500);
+        assert_eq!(escrow.top_up(u64::MAX).unwrap_err(), error!(ErrorCode::InvalidEscrowAmount));
+        assert_eq!(escrow.amount, 1_000_500);
+    }
+
+    #[test]
+    fn amend_refund_counts_claimed_vesting() {
+        let mut escrow = escrow();
+        escrow.amount = 100;
+        escrow.deposited = 80;
+        escrow.vesting = Some(Vesting { steps: 0, claimed: 20, frozen_at: 0 });
+        // 20 claimed + 30 left = 50
+        assert_eq!(escrow.amend_refund(50), 50);
+        // already claimed more than the new amount, everything left goes back
+        escrow.deposited = 40;
+        escrow.vesting = Some(Vesting { steps: 0, claimed: 60, frozen_at: 0 });
+        assert_eq!(escrow.amend_refund(50), 40);
+        escrow.vesting = None;
+        escrow.deposited = 100;
+        assert_eq!(escrow.amend_refund(50), 50);
+    }
+
+    #[test]
+    fn each_period_gets_its_own_dispute_window() {
+        let plain = escrow();
+        assert_eq!(plain.dispute_opens(), plain.deadline);
+        let mut escrow = escrow();
+        escrow.recurring = Some(Recurring { periods: 3, interval: 100, released: 0, auto_release: false });
+        escrow.deadline = 1_300;
+        escrow.judge_deadline = 1_300 + JUDGE_WINDOW;
+        assert_eq!(escrow.dispute_opens(), 1_100);
+        assert!(!escrow.judge_window_closed(1_100 + JUDGE_WINDOW));
+        assert!(escrow.judge_window_closed(1_101 + JUDGE_WINDOW));
+        // once a period is out of the way the next one's clock takes over
+        escrow.record_period(0);
+        assert_eq!(escrow.dispute_opens(), 1_200);
+        assert!(!escrow.judge_window_closed(1_101 + JUDGE_WINDOW));
+    }
+
+    #[test]
+    fn periods_split_evenly_and_the_last_takes_the_rounding() {
+        let mut escrow = escrow();
+        escrow.deposited = 1_000;
+        escrow.recurring = Some(Recurring { periods: 3, interval: 100, released: 0, auto_release: false });
+        let mut paid = Vec::new();
+        loop {
+            let pot = escrow.next_period_amount();
+            paid.push(pot);
+            if escrow.record_period(pot) {
+                break;
+            }
+        }
+        assert_eq!(paid, vec![333, 333, 334]);
+        assert_eq!(escrow.deposited, 0);
+        assert_eq!(escrow.next_period_amount(), 0);
+    }
+
+    #[test]
+    fn periods_unlock_one_interval_apart() {
+        let mut escrow = escrow();
+        escrow.recurring = Some(Recurring { periods: 3, interval: 100, released: 0, auto_release: true });
+        assert_eq!(escrow.next_period_unlocks(), 1_100);
+        escrow.record_period(escrow.next_period_amount());
+        assert_eq!(escrow.next_period_unlocks(), 1_200);
+        escrow.recurring = None;
+        assert_eq!(escrow.next_period_unlocks(), i64::MAX);
+    }
+
+    #[test]
+    fn cancel_owes_unlocked_periods_only_with_auto_release() {
+        let mut escrow = escrow();
+        escrow.deposited = 1_000;
+        escrow.recurring = Some(Recurring { periods: 3, interval: 100, released: 0, auto_release: true });
+        assert_eq!(escrow.unlocked_periods_amount(1_099), 0);
+        assert_eq!(escrow.unlocked_periods_amount(1_200), 666);
+        assert_eq!(escrow.unlocked_periods_amount(9_000), 1_000);
+        // one period already went out, so only what's left is owed
+        escrow.record_period(333);
+        assert_eq!(escrow.unlocked_periods_amount(1_200), 333);
+
+        escrow.recurring.as_mut().unwrap().auto_release = false;
+        assert_eq!(escrow.unlocked_periods_amount(9_000), 0);
+    }
+
+    fn split_escrow(shares: &[u16]) -> EscrowAccount {
+        let mut escrow = escrow();
+        escrow.payees = shares
+            .iter()
+            .map(|bps| PayeeShare { payee: Pubkey::new_unique(), bps: *bps, disputed: false, settled: false })
+            .collect();
+        escrow.payee = escrow.payees[0].payee;
+        escrow
+    }
+
+    #[test]
+    fn split_gives_the_last_open_payee_the_rounding() {
+        let escrow = split_escrow(&[3333, 3333, 3334]);
+        let cuts: Vec<u64> = escrow.split_among_payees(1_000).into_iter().map(|(_, cut)| cut).collect();
+        assert_eq!(cuts, vec![333, 333, 334]);
+        assert_eq!(escrow.split_among_payees(1).into_iter().map(|(_, cut)| cut).sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn settled_shares_drop_out_of_the_split() {
+        let mut escrow = split_escrow(&[5000, 3000, 2000]);
+        escrow.deposited = 1_000;
+        let first = escrow.payees[0].payee;
+        let pot = escrow.share_of(first);
+        assert_eq!(pot, 500);
+        assert!(!escrow.settle_share(first, pot));
+        assert_eq!(escrow.deposited, 500);
+        assert_eq!(escrow.share_of(first), 0);
+        // what's left is shared 3:2 between the two still open
+        let cuts: Vec<u64> = escrow.split_among_payees(escrow.deposited).into_iter().map(|(_, cut)| cut).collect();
+        assert_eq!(cuts, vec![300, 200]);
+
+        escrow.payees[1].disputed = true;
+        assert!(escrow.has_disputed_share());
+        let (second, third) = (escrow.payees[1].payee, escrow.payees[2].payee);
+        assert!(!escrow.settle_share(second, 300));
+        assert!(!escrow.has_disputed_share());
+        assert!(escrow.settle_share(third, 200));
+        assert_eq!(escrow.deposited, 0);
+    }
+
+    #[test]
+    fn judge_window_closes_when_recover_opens() {
+        let escrow = escrow();
+        assert!(!escrow.judge_window_closed(escrow.judge_deadline));
+        assert!(escrow.judge_window_closed(escrow.judge_deadline + 1));
+    }
+
+    #[test]
+    fn approver_cant_be_on_the_payee_side() {
+        let mut escrow = split_escrow(&[6000, 4000]);
+        let payee = escrow.payee;
+        let other_payee = escrow.payees[1].payee;
+        assert_eq!(escrow.set_approver(Some(payee)).unwrap_err(), error!(ErrorCode::InvalidApprover));
+        assert_eq!(escrow.set_approver(Some(other_payee)).unwrap_err(), error!(ErrorCode::InvalidApprover));
+        assert!(escrow.approver.is_none());
+
+        let manager = Pubkey::new_unique();
+        escrow.set_approver(Some(manager)).unwrap();
+        assert_eq!(escrow.approver, Some(manager));
+        escrow.set_approver(None).unwrap();
+        assert!(escrow.approver.is_none());
+    }
+
+
+    #[test]
+    fn tax_tiers_unlock_the_lowest_tax_reached() {
+        let tiers = TaxTiersAccount {
+            mint: Pubkey::default(),
+            tiers: vec![
+                TaxTier { threshold: 1_000, tax: 80 },
+                TaxTier { threshold: 10_000, tax: 50 },
+                TaxTier { threshold: 100_000, tax: 50 },
+            ],
+            bump: 255,
+        };
+        assert_eq!(tiers.tax_for(999, 100), 100);
+        assert_eq!(tiers.tax_for(1_000, 100), 80);
+        assert_eq!(tiers.tax_for(50_000, 100), 50);
+        assert_eq!(tiers.tax_for(u64::MAX, 100), 50);
+        // a tier never charges more than the flat tax
+        assert_eq!(tiers.tax_for(50_000, 30), 30);
+    }
+
+    #[test]
+    fn escrow_tax_counts_this_escrow_towards_the_tiers() {
+        let config = config();
+        let tiers = TaxTiersAccount { mint: Pubkey::default(), tiers: vec![TaxTier { threshold: 10_000, tax: 50 }], bump: 255 };
+        let volume = VolumeAccount { wallet: Pubkey::new_unique(), mint: Pubkey::default(), volume: 6_000, bump: 255 };
+        assert_eq!(config.escrow_tax(None, Some(&volume), 1_000_000), 100);
+        assert_eq!(config.escrow_tax(Some(&tiers), None, 9_999), 100);
+        assert_eq!(config.escrow_tax(Some(&tiers), None, 10_000), 50);
+        assert_eq!(config.escrow_tax(Some(&tiers), Some(&volume), 3_999), 100);
+        assert_eq!(config.escrow_tax(Some(&tiers), Some(&volume), 4_000), 50);
+    }
+
+    #[test]
+    fn amount_has_to_carry_a_unit_of_tax() {
+        assert_eq!(EscrowAccount::check_amount(99, 100).unwrap_err(), error!(ErrorCode::InvalidEscrowAmount));
+        EscrowAccount::check_amount(100, 100).unwrap();
+        assert_eq!(EscrowAccount::check_amount(u64::MAX, 0).unwrap_err(), error!(ErrorCode::InvalidEscrowAmount));
+        EscrowAccount::check_amount(u64::MAX, 2000).unwrap();
+    }
+
+
+    #[test]
+    fn judge_fee_clamps_to_creation_bounds() {
+        let mut escrow = escrow();
+        // 5% judge fee, between 1_000 and 20_000 units
+        escrow.min_fee = 1_000;
+        escrow.max_fee = 20_000;
+        assert_eq!(escrow.judge_fee(100_000), 5_000);
+        assert_eq!(escrow.judge_fee(10_000), 1_000);
+        assert_eq!(escrow.judge_fee(500), 500);
+        assert_eq!(escrow.judge_fee(1_000_000), 20_000);
+        escrow.nft = true;
+        assert_eq!(escrow.judge_fee(1_000_000), 0);
+    }
+
+
+    #[test]
+    fn judge_share_rounds_down_and_treasury_keeps_the_dust() {
+        let mut config = config();
+        assert_eq!(config.split_judge_fee(1_000), (500, 500));
+        assert_eq!(config.split_judge_fee(1), (0, 1));
+        config.judge_share = 3333;
+        assert_eq!(config.split_judge_fee(10), (3, 7));
+        config.judge_share = 10000;
+        assert_eq!(config.split_judge_fee(u64::MAX), (u64::MAX, 0));
+        config.judge_share = 0;
+        assert_eq!(config.split_judge_fee(1_000), (0, 1_000));
+    }
+
+
+    // config with `update` queued on its own, executable from `eta`
+    fn queued_mint_policy(update: MintPolicyUpdate, eta: i64) -> ConfigAccount {
+        let mut config = config();
+        config.queued_update = Some(ConfigUpdateArgs {
+            treasury: None,
+            pending_judge: None,
+            pending_admin: None,
+            tax: None,
+            fee: None,
+            judge_share: None,
+            timelock: None,
+            guardian: None,
+            mint_policy: Some(update),
+        });
+        config.queued_eta = eta;
+        config
+    }
+
+    #[test]
+    fn allowlisting_takes_exactly_the_queued_mint() {
+        let mint = Pubkey::new_unique();
+        let mut config = queued_mint_policy(MintPolicyUpdate::AllowMint { mint }, 1_000);
+        let other = MintPolicyUpdate::AllowMint { mint: Pubkey::new_unique() };
+        assert_eq!(config.take_mint_policy(other, 1_000).unwrap_err(), error!(ErrorCode::MintPolicyMismatch));
+        let bounds = MintPolicyUpdate::FeeBounds { mint, min_fee: 0, max_fee: 0 };
+        assert_eq!(config.take_mint_policy(bounds, 1_000).unwrap_err(), error!(ErrorCode::MintPolicyMismatch));
+        let allow = MintPolicyUpdate::AllowMint { mint };
+        assert_eq!(config.take_mint_policy(allow.clone(), 999).unwrap_err(), error!(ErrorCode::ConfigUpdateTimelocked));
+        config.take_mint_policy(allow.clone(), 1_000).unwrap();
+        assert!(config.queued_update.is_none());
+        // applied once, gone
+        assert_eq!(config.take_mint_policy(allow, 1_000).unwrap_err(), error!(ErrorCode::NoQueuedConfigUpdate));
+    }
+
+
+    #[test]
+    fn tax_tiers_and_fee_bounds_apply_only_as_queued() {
+        let mint = Pubkey::new_unique();
+        let tiers = vec![TaxTier { threshold: 1_000, tax: 80 }, TaxTier { threshold: 10_000, tax: 50 }];
+        let queued = MintPolicyUpdate::TaxTiers { mint, tiers: tiers.clone() };
+        let mut config = queued_mint_policy(queued.clone(), 1_000);
+        let fewer = MintPolicyUpdate::TaxTiers { mint, tiers: tiers[..1].to_vec() };
+        assert_eq!(config.take_mint_policy(fewer, 1_000).unwrap_err(), error!(ErrorCode::MintPolicyMismatch));
+        config.take_mint_policy(queued, 1_000).unwrap();
+
+        let bounds = MintPolicyUpdate::FeeBounds { mint, min_fee: 10, max_fee: 100 };
+        let mut config = queued_mint_policy(bounds.clone(), 1_000);
+        let wider = MintPolicyUpdate::FeeBounds { mint, min_fee: 10, max_fee: 1_000 };
+        assert_eq!(config.take_mint_policy(wider, 2_000).unwrap_err(), error!(ErrorCode::MintPolicyMismatch));
+        assert_eq!(config.take_mint_policy(bounds.clone(), 999).unwrap_err(), error!(ErrorCode::ConfigUpdateTimelocked));
+        config.take_mint_policy(bounds, 1_000).unwrap();
+        assert!(config.queued_update.is_none());
+    }
+
+    #[test]
+    fn mint_policy_rejects_bad_tiers_and_bounds() {
+        let mint = Pubkey::new_unique();
+        let tiers = |tiers: &[(u64, u16)]| MintPolicyUpdate::TaxTiers {
+            mint,
+            tiers: tiers.iter().map(|&(threshold, tax)| TaxTier { threshold, tax }).collect(),
+        };
+        tiers(&[(1_000, 80), (10_000, 50), (100_000, 50)]).validate().unwrap();
+        assert_eq!(tiers(&[(1_000, 80), (1_000, 50)]).validate().unwrap_err(), error!(ErrorCode::InvalidTaxTiers));
+        assert_eq!(tiers(&[(1_000, 50), (10_000, 80)]).validate().unwrap_err(), error!(ErrorCode::InvalidTaxTiers));
+        assert_eq!(tiers(&[(1_000, 2001)]).validate().unwrap_err(), error!(ErrorCode::TaxTooHigh));
+        let too_many: Vec<(u64, u16)> = (0..=MAX_TAX_TIERS as u64).map(|i| (i + 1, 100)).collect();
+        assert_eq!(tiers(&too_many).validate().unwrap_err(), error!(ErrorCode::TooManyTaxTiers));
+
+        MintPolicyUpdate::FeeBounds { mint, min_fee: 100, max_fee: 100 }.validate().unwrap();
+        let inverted = MintPolicyUpdate::FeeBounds { mint, min_fee: 101, max_fee: 100 };
+        assert_eq!(inverted.validate().unwrap_err(), error!(ErrorCode::InvalidFeeBounds));
+    }
+
+
+    #[test]
+    fn guardian_can_only_add_pause_flags() {
+        let mut config = config();
+        let (admin, guardian) = (config.admin, config.guardian);
+        config.check_pause(guardian, PAUSE_DEPOSIT).unwrap();
+        config.paused = PAUSE_DEPOSIT;
+        config.check_pause(guardian, PAUSE_ALL).unwrap();
+        config.check_pause(guardian, PAUSE_DEPOSIT).unwrap();
+        assert_eq!(config.check_pause(guardian, PAUSE_CREATE).unwrap_err(), error!(ErrorCode::GuardianCannotUnpause));
+        assert_eq!(config.check_pause(guardian, 0).unwrap_err(), error!(ErrorCode::GuardianCannotUnpause));
+        config.check_pause(admin, 0).unwrap();
+        assert_eq!(config.check_pause(admin, 4).unwrap_err(), error!(ErrorCode::InvalidPauseFlags));
+    }
+}